@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::net::TcpStream;
+
+pub enum Body {
+    Bytes(Vec<u8>),
+    Reader {
+        reader: Box<dyn Read + Send>,
+        length: Option<u64>,
+    },
+    File {
+        file: File,
+        offset: u64,
+        length: u64,
+    },
+}
+
+const CHUNK_SIZE: usize = 16 * 1024;
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+const SENDFILE_THRESHOLD: u64 = 64 * 1024;
+
+impl Body {
+    pub fn empty() -> Self {
+        Body::Bytes(Vec::new())
+    }
+
+    pub fn reader<R: Read + Send + 'static>(reader: R, length: Option<u64>) -> Self {
+        Body::Reader {
+            reader: Box::new(reader),
+            length,
+        }
+    }
+
+    pub fn file(file: File) -> io::Result<Self> {
+        let length = file.metadata()?.len();
+        Ok(Body::File {
+            file,
+            offset: 0,
+            length,
+        })
+    }
+
+    pub fn chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Vec<u8>>,
+        I::IntoIter: Send + 'static,
+    {
+        Self::reader(ChunkReader::new(chunks.into_iter()), None)
+    }
+
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::Reader { length, .. } => *length,
+            Body::File { length, .. } => Some(*length),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes),
+            Body::Reader { .. } | Body::File { .. } => None,
+        }
+    }
+
+    pub fn write_to(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        match self {
+            Body::Bytes(bytes) => writer.write_all(bytes),
+            Body::Reader { reader, length: Some(length) } => {
+                let copied = io::copy(&mut reader.take(*length), writer)?;
+                if copied < *length {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                }
+                Ok(())
+            }
+            Body::Reader { reader, length: None } => {
+                let mut buffer = vec![0; CHUNK_SIZE];
+                loop {
+                    let read = reader.read(&mut buffer)?;
+                    if read == 0 {
+                        break;
+                    }
+                    write!(writer, "{read:X}\r\n")?;
+                    writer.write_all(&buffer[..read])?;
+                    writer.write_all(b"\r\n")?;
+                }
+                writer.write_all(b"0\r\n\r\n")
+            }
+            Body::File { file, offset, length } => {
+                file.seek(SeekFrom::Start(*offset))?;
+                let copied = io::copy(&mut Read::by_ref(file).take(*length), writer)?;
+                if copied < *length {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn send(&mut self, stream: &TcpStream, writer: &mut impl Write) -> io::Result<()> {
+        #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+        if let Body::File { file, offset, length } = self
+            && *length >= SENDFILE_THRESHOLD
+        {
+            writer.flush()?;
+            return sendfile(stream, file, *offset, *length);
+        }
+
+        #[cfg(not(all(target_os = "linux", target_pointer_width = "64")))]
+        let _ = stream;
+
+        self.write_to(writer)?;
+        writer.flush()
+    }
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+fn sendfile(stream: &TcpStream, file: &File, offset: u64, length: u64) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    unsafe extern "C" {
+        fn sendfile(out_fd: i32, in_fd: i32, offset: *mut i64, count: usize) -> isize;
+    }
+
+    let mut offset = offset as i64;
+    let mut remaining = length;
+
+    while remaining > 0 {
+        let count = remaining.min(0x7fff_f000) as usize;
+        // SAFETY: both descriptors stay open for the duration of the call and `offset` is a valid pointer.
+        let sent = unsafe { sendfile(stream.as_raw_fd(), file.as_raw_fd(), &mut offset, count) };
+        if sent < 0 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(e);
+        }
+        if sent == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        remaining -= sent as u64;
+    }
+
+    Ok(())
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Body {
+    fn from(bytes: &[u8]) -> Self {
+        Body::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Body::Bytes(text.into_bytes())
+    }
+}
+
+impl From<&str> for Body {
+    fn from(text: &str) -> Self {
+        Body::Bytes(text.as_bytes().to_vec())
+    }
+}
+
+struct ChunkReader<I> {
+    chunks: I,
+    current: Vec<u8>,
+    position: usize,
+}
+
+impl<I> ChunkReader<I> {
+    fn new(chunks: I) -> Self {
+        ChunkReader {
+            chunks,
+            current: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<I> Read for ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: Into<Vec<u8>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position >= self.current.len() {
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.current = chunk.into();
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let available = &self.current[self.position..];
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(mut body: Body) -> io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+        body.write_to(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn sized_reader_writes_exactly_its_length() {
+        assert_eq!(written(Body::reader(&b"hello world"[..], Some(5))).unwrap(), b"hello");
+        assert_eq!(written(Body::reader(&b"hello"[..], Some(5))).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn sized_reader_rejects_short_reads() {
+        let error = written(Body::reader(&b"hell"[..], Some(5))).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn unsized_bodies_use_chunked_encoding() {
+        assert_eq!(written(Body::chunks(["ab", "", "cde"])).unwrap(), b"2\r\nab\r\n3\r\ncde\r\n0\r\n\r\n");
+        assert_eq!(written(Body::reader(&b""[..], None)).unwrap(), b"0\r\n\r\n");
+    }
+}
@@ -0,0 +1,478 @@
+use crate::body::Body;
+use crate::mime;
+use crate::request::Request;
+use crate::response::Response;
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const HASH_BITS: u32 = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Encoding::Gzip => gzip(data),
+            Encoding::Deflate => zlib(data),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Compression {
+    min_size: usize,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compression {
+    pub fn new() -> Self {
+        Compression { min_size: 1024 }
+    }
+
+    pub fn min_size(mut self, bytes: usize) -> Self {
+        self.min_size = bytes;
+        self
+    }
+
+    pub(crate) fn apply(&self, request: &Request, response: &mut Response) {
+        if response.status_code != 200 || response.headers.contains("Content-Encoding") {
+            return;
+        }
+        let compressible = response.headers.get("Content-Type").is_some_and(mime::is_text);
+        let length = match response.body.as_bytes() {
+            Some(body) if compressible => body.len(),
+            _ => return,
+        };
+        if length < self.min_size {
+            return;
+        }
+
+        add_vary(response, "Accept-Encoding");
+        let Some(encoding) = preferred_encoding(request) else {
+            return;
+        };
+
+        let Some(body) = response.body.as_bytes() else {
+            return;
+        };
+        let encoded = encoding.encode(body);
+        if encoded.len() >= body.len() {
+            return;
+        }
+
+        if let Some(etag) = response.headers.get("ETag").filter(|etag| !etag.starts_with("W/")) {
+            let weak = format!("W/{etag}");
+            response.set_header("ETag", &weak);
+        }
+        response.set_header("Content-Encoding", encoding.name());
+        response.set_body(Body::Bytes(encoded));
+    }
+}
+
+pub(crate) fn accepts(request: &Request, coding: &str) -> bool {
+    let Some(header) = request.get_header("Accept-Encoding") else {
+        return false;
+    };
+
+    let mut wildcard = None;
+    for entry in header.split(',') {
+        let mut parts = entry.split(';');
+        let name = parts.next().unwrap_or_default().trim();
+        let quality = parts
+            .find_map(|parameter| parameter.trim().strip_prefix("q="))
+            .and_then(|quality| quality.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if name.eq_ignore_ascii_case(coding) {
+            return quality > 0.0;
+        }
+        if name == "*" {
+            wildcard = Some(quality);
+        }
+    }
+
+    wildcard.is_some_and(|quality| quality > 0.0)
+}
+
+pub(crate) fn add_vary(response: &mut Response, header: &str) {
+    let existing = response.headers.get("Vary").unwrap_or_default().to_string();
+    let already_listed = existing
+        .split(',')
+        .any(|name| name.trim() == "*" || name.trim().eq_ignore_ascii_case(header));
+
+    if existing.is_empty() {
+        response.set_header("Vary", header);
+    } else if !already_listed {
+        response.set_header("Vary", &format!("{existing}, {header}"));
+    }
+}
+
+fn preferred_encoding(request: &Request) -> Option<Encoding> {
+    if accepts(request, "gzip") {
+        Some(Encoding::Gzip)
+    } else if accepts(request, "deflate") {
+        Some(Encoding::Deflate)
+    } else {
+        None
+    }
+}
+
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff];
+    output.extend(deflate(data));
+    output.extend(crc32(data).to_le_bytes());
+    output.extend((data.len() as u32).to_le_bytes());
+    output
+}
+
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x78, 0x01];
+    output.extend(deflate(data));
+    output.extend(adler32(data).to_be_bytes());
+    output
+}
+
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new();
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    let hash_size = 1 << HASH_BITS;
+    let mut head = vec![usize::MAX; hash_size];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let mut position = 0;
+
+    while position < data.len() {
+        let (length, distance) = longest_match(data, position, &head, &prev);
+
+        if length >= MIN_MATCH {
+            write_length(&mut writer, length);
+            write_distance(&mut writer, distance);
+            for offset in position..position + length {
+                insert_hash(data, offset, &mut head, &mut prev);
+            }
+            position += length;
+        } else {
+            write_literal(&mut writer, u16::from(data[position]));
+            insert_hash(data, position, &mut head, &mut prev);
+            position += 1;
+        }
+    }
+
+    write_literal(&mut writer, 256);
+    writer.finish()
+}
+
+fn hash(data: &[u8], position: usize) -> usize {
+    let value = u32::from(data[position]) << 16 | u32::from(data[position + 1]) << 8 | u32::from(data[position + 2]);
+    (value.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+}
+
+fn insert_hash(data: &[u8], position: usize, head: &mut [usize], prev: &mut [usize]) {
+    if position + MIN_MATCH > data.len() {
+        return;
+    }
+    let hash = hash(data, position);
+    prev[position % WINDOW_SIZE] = head[hash];
+    head[hash] = position;
+}
+
+fn longest_match(data: &[u8], position: usize, head: &[usize], prev: &[usize]) -> (usize, usize) {
+    if position + MIN_MATCH > data.len() {
+        return (0, 0);
+    }
+
+    let max_length = MAX_MATCH.min(data.len() - position);
+    let mut candidate = head[hash(data, position)];
+    let mut best = (0, 0);
+    let mut chain = 0;
+
+    while candidate != usize::MAX && chain < MAX_CHAIN {
+        let distance = position - candidate;
+        if distance == 0 || distance > WINDOW_SIZE {
+            break;
+        }
+
+        let length = data[candidate..]
+            .iter()
+            .zip(&data[position..position + max_length])
+            .take_while(|(left, right)| left == right)
+            .count();
+        if length > best.0 {
+            best = (length, distance);
+            if length == max_length {
+                break;
+            }
+        }
+
+        let next = prev[candidate % WINDOW_SIZE];
+        if next == usize::MAX || next >= candidate {
+            break;
+        }
+        candidate = next;
+        chain += 1;
+    }
+
+    best
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u16) {
+    let (code, length) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xC0 + symbol - 280, 8),
+    };
+    writer.write_code(code, length);
+}
+
+fn write_length(writer: &mut BitWriter, length: usize) {
+    let index = LENGTH_BASE.iter().rposition(|base| usize::from(*base) <= length).unwrap_or(0);
+    write_literal(writer, 257 + index as u16);
+    writer.write_bits((length - usize::from(LENGTH_BASE[index])) as u32, LENGTH_EXTRA[index]);
+}
+
+fn write_distance(writer: &mut BitWriter, distance: usize) {
+    let index = DISTANCE_BASE.iter().rposition(|base| usize::from(*base) <= distance).unwrap_or(0);
+    writer.write_code(index as u16, 5);
+    writer.write_bits((distance - usize::from(DISTANCE_BASE[index])) as u32, DISTANCE_EXTRA[index]);
+}
+
+struct BitWriter {
+    output: Vec<u8>,
+    buffer: u64,
+    count: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            output: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn write_bits(&mut self, value: u32, bits: u8) {
+        self.buffer |= u64::from(value) << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.output.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn write_code(&mut self, code: u16, bits: u8) {
+        let reversed = code.reverse_bits() >> (16 - u32::from(bits));
+        self.write_bits(u32::from(reversed), bits);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.output.push(self.buffer as u8);
+        }
+        self.output
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct BitReader<'a> {
+        data: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bit(&mut self) -> u32 {
+            let byte = self.data[self.position / 8];
+            let bit = u32::from(byte >> (self.position % 8) & 1);
+            self.position += 1;
+            bit
+        }
+
+        fn bits(&mut self, count: u8) -> u32 {
+            (0..count).fold(0, |value, i| value | self.bit() << i)
+        }
+
+        fn code(&mut self, count: u8) -> u32 {
+            (0..count).fold(0, |code, _| code << 1 | self.bit())
+        }
+
+        fn literal(&mut self) -> u16 {
+            let code = self.code(7);
+            if code <= 0x17 {
+                return 256 + code as u16;
+            }
+            let code = code << 1 | self.bit();
+            match code {
+                0x30..=0xBF => (code - 0x30) as u16,
+                0xC0..=0xC7 => (280 + code - 0xC0) as u16,
+                _ => (144 + (code << 1 | self.bit()) - 0x190) as u16,
+            }
+        }
+    }
+
+    fn inflate_fixed(data: &[u8]) -> Vec<u8> {
+        let mut reader = BitReader { data, position: 0 };
+        assert_eq!(reader.bits(1), 1, "expected a final block");
+        assert_eq!(reader.bits(2), 1, "expected fixed Huffman codes");
+
+        let mut output = Vec::new();
+        loop {
+            let symbol = reader.literal();
+            match symbol {
+                0..=255 => output.push(symbol as u8),
+                256 => break,
+                _ => {
+                    let index = usize::from(symbol - 257);
+                    let length = usize::from(LENGTH_BASE[index]) + reader.bits(LENGTH_EXTRA[index]) as usize;
+                    let index = reader.code(5) as usize;
+                    let distance = usize::from(DISTANCE_BASE[index]) + reader.bits(DISTANCE_EXTRA[index]) as usize;
+                    for _ in 0..length {
+                        output.push(output[output.len() - distance]);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(reader.position.div_ceil(8), data.len(), "trailing bytes after the final block");
+        output
+    }
+
+    fn samples() -> Vec<Vec<u8>> {
+        let mut state = 0x2545_F491u32;
+        let noise: Vec<u8> = (0..70_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(500).into_bytes();
+        let mut far_repeat = noise[..40_000].to_vec();
+        far_repeat.extend_from_slice(&noise[..1_000]);
+
+        vec![
+            Vec::new(),
+            b"a".to_vec(),
+            b"abc".to_vec(),
+            vec![0; 100_000],
+            (0..=255).collect(),
+            text,
+            noise,
+            far_repeat,
+        ]
+    }
+
+    #[test]
+    fn crc32_known_answers() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"The quick brown fox jumps over the lazy dog"), 0x414F_A339);
+    }
+
+    #[test]
+    fn adler32_known_answers() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&[0xFF; 100_000]), 0x149A_302C);
+    }
+
+    #[test]
+    fn deflate_round_trip() {
+        for sample in samples() {
+            assert_eq!(inflate_fixed(&deflate(&sample)), sample);
+        }
+    }
+
+    #[test]
+    fn deflate_compresses_repetitive_input() {
+        assert!(deflate(&[0; 100_000]).len() < 1_000);
+        assert!(deflate("abc".repeat(1_000).as_bytes()).len() < 100);
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        for sample in samples() {
+            let encoded = gzip(&sample);
+            let (header, rest) = encoded.split_at(10);
+            let (body, trailer) = rest.split_at(rest.len() - 8);
+
+            assert_eq!(&header[..4], &[0x1f, 0x8b, 0x08, 0x00]);
+            assert_eq!(inflate_fixed(body), sample);
+            assert_eq!(trailer[..4], crc32(&sample).to_le_bytes());
+            assert_eq!(trailer[4..], (sample.len() as u32).to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn zlib_round_trip() {
+        for sample in samples() {
+            let encoded = zlib(&sample);
+            let (body, trailer) = encoded[2..].split_at(encoded.len() - 6);
+
+            assert_eq!(u16::from_be_bytes([encoded[0], encoded[1]]) % 31, 0);
+            assert_eq!(encoded[0] & 0x0f, 8);
+            assert_eq!(inflate_fixed(body), sample);
+            assert_eq!(trailer, adler32(&sample).to_be_bytes());
+        }
+    }
+}
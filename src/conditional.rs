@@ -0,0 +1,88 @@
+use std::time::SystemTime;
+use crate::body::Body;
+use crate::date::parse_http_date;
+use crate::header::HeaderMap;
+use crate::request::Request;
+use crate::response::Response;
+use crate::status::StatusCode;
+
+const PRESERVED_HEADERS: [&str; 8] = [
+    "Cache-Control",
+    "Content-Location",
+    "Date",
+    "ETag",
+    "Expires",
+    "Last-Modified",
+    "Set-Cookie",
+    "Vary",
+];
+
+pub(crate) fn apply_conditional(request: &Request, response: &mut Response) {
+    if response.status_code != 200 || !matches!(request.method.as_str(), "GET" | "HEAD") {
+        return;
+    }
+
+    if is_not_modified(request, response) {
+        let mut headers = HeaderMap::new();
+        for name in PRESERVED_HEADERS {
+            for value in response.headers.get_all(name) {
+                headers.append(name, value).expect("header was already validated");
+            }
+        }
+
+        *response = Response {
+            status_code: StatusCode::NOT_MODIFIED,
+            status_text: StatusCode::NOT_MODIFIED.canonical_reason().to_string(),
+            headers,
+            body: Body::empty(),
+        };
+    }
+}
+
+pub(crate) fn if_range_matches(request: &Request, response: &Response) -> bool {
+    let Some(condition) = request.get_header("If-Range") else {
+        return true;
+    };
+    let condition = condition.trim();
+
+    if condition.starts_with('"') {
+        return response
+            .headers
+            .get("ETag")
+            .is_some_and(|etag| !etag.starts_with("W/") && etag == condition);
+    }
+
+    match (parse_http_date(condition), last_modified(response)) {
+        (Some(since), Some(modified)) => modified == since,
+        _ => false,
+    }
+}
+
+fn is_not_modified(request: &Request, response: &Response) -> bool {
+    if let Some(if_none_match) = request.get_header("If-None-Match") {
+        let Some(etag) = response.headers.get("ETag") else {
+            return false;
+        };
+        return if_none_match.trim() == "*"
+            || if_none_match
+                .split(',')
+                .any(|candidate| weak_eq(candidate.trim(), etag));
+    }
+
+    if let Some(if_modified_since) = request.get_header("If-Modified-Since") {
+        return match (parse_http_date(if_modified_since), last_modified(response)) {
+            (Some(since), Some(modified)) => modified <= since,
+            _ => false,
+        };
+    }
+
+    false
+}
+
+fn last_modified(response: &Response) -> Option<SystemTime> {
+    response.headers.get("Last-Modified").and_then(parse_http_date)
+}
+
+fn weak_eq(left: &str, right: &str) -> bool {
+    left.trim_start_matches("W/") == right.trim_start_matches("W/")
+}
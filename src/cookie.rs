@@ -0,0 +1,369 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::crypto::{base64_url_decode, base64_url_encode, chacha20, constant_time_eq, hmac_sha256, random_bytes};
+use crate::date::format_http_date;
+use crate::header::{is_token, InvalidHeader};
+use crate::request::Request;
+
+const MIN_SECRET_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+const TAG_LENGTH: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    expires: Option<SystemTime>,
+    max_age: Option<Duration>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    pub fn new(name: &str, value: &str) -> Self {
+        Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            path: None,
+            domain: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    pub fn expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    pub fn removal(mut self) -> Self {
+        self.value.clear();
+        self.expires = Some(UNIX_EPOCH);
+        self.max_age = Some(Duration::ZERO);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn set_value(&mut self, value: &str) {
+        self.value = value.to_string();
+    }
+
+    pub fn validate(&self) -> Result<(), InvalidHeader> {
+        if !is_token(&self.name) {
+            return Err(InvalidHeader::Name(self.name.clone()));
+        }
+
+        let valid_value = self.value.bytes().all(is_cookie_octet);
+        let valid_attributes = [&self.path, &self.domain]
+            .into_iter()
+            .flatten()
+            .all(|attribute| attribute.bytes().all(|byte| byte.is_ascii_graphic() && byte != b';'));
+        if !valid_value || !valid_attributes {
+            return Err(InvalidHeader::Value(self.name.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", format_http_date(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if self.secure || self.same_site == Some(SameSite::None) {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct CookieKey {
+    signing: [u8; 32],
+    encryption: [u8; 32],
+    authentication: [u8; 32],
+}
+
+impl CookieKey {
+    fn derive(secret: &[u8]) -> Self {
+        assert!(
+            secret.len() >= MIN_SECRET_LENGTH,
+            "Cookie secrets must be at least {MIN_SECRET_LENGTH} bytes long"
+        );
+        CookieKey {
+            signing: hmac_sha256(secret, b"syne cookie signing"),
+            encryption: hmac_sha256(secret, b"syne cookie encryption"),
+            authentication: hmac_sha256(secret, b"syne cookie authentication"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CookieJar {
+    keys: Vec<CookieKey>,
+}
+
+impl CookieJar {
+    pub fn new(secret: &[u8]) -> Self {
+        CookieJar {
+            keys: vec![CookieKey::derive(secret)],
+        }
+    }
+
+    pub fn with_previous_key(mut self, secret: &[u8]) -> Self {
+        self.keys.push(CookieKey::derive(secret));
+        self
+    }
+
+    pub fn sign(&self, mut cookie: Cookie) -> Cookie {
+        let encoded = base64_url_encode(cookie.value.as_bytes());
+        let signature = hmac_sha256(&self.keys[0].signing, signed_message(&cookie.name, &encoded).as_bytes());
+        cookie.value = format!("{encoded}.{}", base64_url_encode(&signature));
+        cookie
+    }
+
+    pub fn verify(&self, name: &str, value: &str) -> Option<String> {
+        let (encoded, signature) = value.rsplit_once('.')?;
+        let signature = base64_url_decode(signature)?;
+        let message = signed_message(name, encoded);
+
+        self.keys
+            .iter()
+            .any(|key| constant_time_eq(&hmac_sha256(&key.signing, message.as_bytes()), &signature))
+            .then(|| String::from_utf8(base64_url_decode(encoded)?).ok())
+            .flatten()
+    }
+
+    pub fn encrypt(&self, mut cookie: Cookie) -> Cookie {
+        let key = &self.keys[0];
+        let nonce: [u8; NONCE_LENGTH] = random_bytes(NONCE_LENGTH).try_into().expect("nonce has the requested length");
+
+        let mut payload = nonce.to_vec();
+        payload.extend(chacha20(&key.encryption, &nonce, cookie.value.as_bytes()));
+        let tag = hmac_sha256(&key.authentication, &authenticated_message(&cookie.name, &payload));
+        payload.extend(tag);
+
+        cookie.value = base64_url_encode(&payload);
+        cookie
+    }
+
+    pub fn decrypt(&self, name: &str, value: &str) -> Option<String> {
+        let payload = base64_url_decode(value)?;
+        if payload.len() < NONCE_LENGTH + TAG_LENGTH {
+            return None;
+        }
+        let (sealed, tag) = payload.split_at(payload.len() - TAG_LENGTH);
+        let message = authenticated_message(name, sealed);
+
+        let key = self
+            .keys
+            .iter()
+            .find(|key| constant_time_eq(&hmac_sha256(&key.authentication, &message), tag))?;
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let nonce: [u8; NONCE_LENGTH] = nonce.try_into().ok()?;
+        String::from_utf8(chacha20(&key.encryption, &nonce, ciphertext)).ok()
+    }
+
+    pub fn signed(&self, request: &Request, name: &str) -> Option<String> {
+        self.verify(name, request.cookie(name)?)
+    }
+
+    pub fn private(&self, request: &Request, name: &str) -> Option<String> {
+        self.decrypt(name, request.cookie(name)?)
+    }
+}
+
+fn signed_message(name: &str, encoded_value: &str) -> String {
+    format!("{name}={encoded_value}")
+}
+
+fn authenticated_message(name: &str, sealed: &[u8]) -> Vec<u8> {
+    let mut message = format!("{name}=").into_bytes();
+    message.extend_from_slice(sealed);
+    message
+}
+
+pub(crate) fn parse_cookie_header(header: &str) -> impl Iterator<Item = (&str, &str)> {
+    header.split(';').filter_map(|pair| {
+        let (name, value) = pair.split_once('=')?;
+        let name = name.trim();
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        (!name.is_empty()).then_some((name, value))
+    })
+}
+
+fn is_cookie_octet(byte: u8) -> bool {
+    matches!(byte, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT: &[u8] = b"current secret, at least 32 bytes";
+    const PREVIOUS: &[u8] = b"previous secret, at least 32 bytes";
+    const OTHER: &[u8] = b"unrelated secret, at least 32 bytes";
+
+    fn flip_last_char(value: &str) -> String {
+        let mut tampered = value.to_string();
+        let last = tampered.pop().expect("value is not empty");
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        tampered
+    }
+
+    #[test]
+    fn validates_names_as_tokens() {
+        assert!(Cookie::new("session_id-v2", "abc").validate().is_ok());
+        for name in ["", "a b", "a=b", "a;b", "caf\u{e9}"] {
+            assert_eq!(Cookie::new(name, "abc").validate(), Err(InvalidHeader::Name(name.to_string())));
+        }
+        assert_eq!(Cookie::new("a", "b;c").validate(), Err(InvalidHeader::Value("a".to_string())));
+    }
+
+    #[test]
+    fn signed_cookie_round_trip() {
+        let jar = CookieJar::new(CURRENT);
+        let cookie = jar.sign(Cookie::new("user", "alice; admin=1"));
+
+        assert_eq!(jar.verify("user", cookie.value()), Some("alice; admin=1".to_string()));
+    }
+
+    #[test]
+    fn signed_cookie_rejects_tampering() {
+        let jar = CookieJar::new(CURRENT);
+        let value = jar.sign(Cookie::new("user", "alice")).value().to_string();
+        let (_, signature) = value.rsplit_once('.').unwrap();
+        let forged = format!("{}.{signature}", base64_url_encode(b"mallory"));
+
+        assert_eq!(jar.verify("user", &forged), None);
+        assert_eq!(jar.verify("user", &flip_last_char(&value)), None);
+        assert_eq!(jar.verify("other", &value), None);
+        assert_eq!(jar.verify("user", "alice"), None);
+        assert_eq!(CookieJar::new(OTHER).verify("user", &value), None);
+    }
+
+    #[test]
+    fn private_cookie_round_trip() {
+        let jar = CookieJar::new(CURRENT);
+        let first = jar.encrypt(Cookie::new("token", "s3cret"));
+        let second = jar.encrypt(Cookie::new("token", "s3cret"));
+
+        assert!(!first.value().contains("s3cret"));
+        assert_ne!(first.value(), second.value());
+        assert_eq!(jar.decrypt("token", first.value()), Some("s3cret".to_string()));
+    }
+
+    #[test]
+    fn private_cookie_rejects_tampering() {
+        let jar = CookieJar::new(CURRENT);
+        let value = jar.encrypt(Cookie::new("token", "s3cret")).value().to_string();
+        let mut payload = base64_url_decode(&value).unwrap();
+        payload[NONCE_LENGTH] ^= 1;
+
+        assert_eq!(jar.decrypt("token", &base64_url_encode(&payload)), None);
+        assert_eq!(jar.decrypt("token", &value[..value.len() - 4]), None);
+        assert_eq!(jar.decrypt("other", &value), None);
+        assert_eq!(jar.decrypt("token", ""), None);
+        assert_eq!(CookieJar::new(OTHER).decrypt("token", &value), None);
+    }
+
+    #[test]
+    fn rotated_keys_accept_old_cookies_and_issue_new_ones() {
+        let previous = CookieJar::new(PREVIOUS);
+        let signed = previous.sign(Cookie::new("user", "alice"));
+        let private = previous.encrypt(Cookie::new("token", "s3cret"));
+
+        let rotated = CookieJar::new(CURRENT).with_previous_key(PREVIOUS);
+        assert_eq!(rotated.verify("user", signed.value()), Some("alice".to_string()));
+        assert_eq!(rotated.decrypt("token", private.value()), Some("s3cret".to_string()));
+
+        let resigned = rotated.sign(Cookie::new("user", "alice"));
+        assert_eq!(previous.verify("user", resigned.value()), None);
+        assert_eq!(CookieJar::new(CURRENT).verify("user", resigned.value()), Some("alice".to_string()));
+
+        let retired = CookieJar::new(CURRENT);
+        assert_eq!(retired.verify("user", signed.value()), None);
+        assert_eq!(retired.decrypt("token", private.value()), None);
+    }
+}
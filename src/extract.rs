@@ -0,0 +1,247 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::str::FromStr;
+use crate::json::{self, FromJson, JsonError, ToJson};
+use crate::multipart::{Multipart, MultipartError};
+use crate::params::{FieldError, FormError, FromForm, FromQuery, ParamMap};
+use crate::request::Request;
+use crate::response::Response;
+use crate::session::Session;
+use crate::status::StatusCode;
+use crate::error;
+
+pub(crate) type StateMap = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
+pub trait FromRequest: Sized {
+    fn from_request(request: &Request) -> Result<Self, Response>;
+}
+
+pub trait IntoResponse {
+    fn into_response(self) -> Response;
+}
+
+pub trait Handler<Args>: Send + Sync + 'static {
+    fn call(&self, request: &Request) -> Response;
+}
+
+macro_rules! handlers {
+    ($($extractor:ident),*) => {
+        impl<F, R, $($extractor,)*> Handler<($($extractor,)*)> for F
+        where
+            F: Fn($($extractor),*) -> R + Send + Sync + 'static,
+            R: IntoResponse,
+            $($extractor: FromRequest,)*
+        {
+            #[allow(non_snake_case, unused_variables)]
+            fn call(&self, request: &Request) -> Response {
+                $(
+                    let $extractor = match $extractor::from_request(request) {
+                        Ok(value) => value,
+                        Err(response) => return response,
+                    };
+                )*
+                self($($extractor),*).into_response()
+            }
+        }
+    };
+}
+
+handlers!();
+handlers!(A);
+handlers!(A, B);
+handlers!(A, B, C);
+handlers!(A, B, C, D);
+handlers!(A, B, C, D, E);
+handlers!(A, B, C, D, E, G);
+
+pub trait FromPath: Sized {
+    fn from_path(params: &ParamMap) -> Result<Self, FieldError>;
+}
+
+macro_rules! path_scalars {
+    ($($scalar:ty),*) => {
+        $(
+            impl FromPath for $scalar {
+                fn from_path(params: &ParamMap) -> Result<Self, FieldError> {
+                    match params.iter().next() {
+                        Some((name, _)) if params.len() == 1 => params.required(name),
+                        _ => Err(FieldError::new("", &format!("expected 1 path parameter, found {}", params.len()))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+path_scalars!(String, bool, char, f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+macro_rules! path_tuples {
+    ($count:literal: $($element:ident),*) => {
+        impl<$($element,)*> FromPath for ($($element,)*)
+        where
+            $($element: FromStr, $element::Err: std::fmt::Display,)*
+        {
+            fn from_path(params: &ParamMap) -> Result<Self, FieldError> {
+                if params.len() != $count {
+                    return Err(FieldError::new("", &format!("expected {} path parameters, found {}", $count, params.len())));
+                }
+                let mut names = params.iter().map(|(name, _)| name);
+                Ok(($(params.required::<$element>(names.next().unwrap_or_default())?,)*))
+            }
+        }
+    };
+}
+
+path_tuples!(1: A);
+path_tuples!(2: A, B);
+path_tuples!(3: A, B, C);
+path_tuples!(4: A, B, C, D);
+
+impl FromPath for ParamMap {
+    fn from_path(params: &ParamMap) -> Result<Self, FieldError> {
+        Ok(params.clone())
+    }
+}
+
+pub struct Path<T>(pub T);
+
+impl<T: FromPath> FromRequest for Path<T> {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        T::from_path(&request.params).map(Path).map_err(Response::from)
+    }
+}
+
+pub struct Query<T>(pub T);
+
+impl<T: FromQuery> FromRequest for Query<T> {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        request.query().map(Query).map_err(Response::from)
+    }
+}
+
+pub struct Form<T>(pub T);
+
+impl<T: FromForm> FromRequest for Form<T> {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        request.form().map(Form).map_err(Response::from)
+    }
+}
+
+pub struct Json<T>(pub T);
+
+impl<T: FromJson> FromRequest for Json<T> {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        request.json().map(Json).map_err(Response::from)
+    }
+}
+
+impl<T: ToJson> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        Response::json_value(200, &self.0.to_json())
+    }
+}
+
+pub struct State<T>(pub T);
+
+impl<T: Clone + Send + Sync + 'static> FromRequest for State<T> {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        match request.state::<T>() {
+            Some(state) => Ok(State(state.clone())),
+            None => {
+                error!("No state of type {} registered on the router", std::any::type_name::<T>());
+                Err(Response::with_status(StatusCode::INTERNAL_SERVER_ERROR, "Missing application state"))
+            }
+        }
+    }
+}
+
+impl FromRequest for Session {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        match request.session() {
+            Some(session) => Ok(session.clone()),
+            None => {
+                error!("Sessions are not enabled on the router");
+                Err(Response::with_status(StatusCode::INTERNAL_SERVER_ERROR, "Sessions are not enabled"))
+            }
+        }
+    }
+}
+
+impl FromRequest for Multipart {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        request.multipart().map_err(Response::from)
+    }
+}
+
+impl<T: FromRequest> FromRequest for Option<T> {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        Ok(T::from_request(request).ok())
+    }
+}
+
+impl<T: FromRequest> FromRequest for Result<T, Response> {
+    fn from_request(request: &Request) -> Result<Self, Response> {
+        Ok(T::from_request(request))
+    }
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> Response {
+        Response::text(200, self)
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Response {
+        Response::text(200, &self)
+    }
+}
+
+impl IntoResponse for StatusCode {
+    fn into_response(self) -> Response {
+        Response::with_status(self, "")
+    }
+}
+
+impl IntoResponse for json::Json {
+    fn into_response(self) -> Response {
+        Response::json_value(200, &self)
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for (StatusCode, T) {
+    fn into_response(self) -> Response {
+        let mut response = self.1.into_response();
+        response.set_status(self.0);
+        response
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+}
+
+macro_rules! error_responses {
+    ($($error:ty),*) => {
+        $(
+            impl IntoResponse for $error {
+                fn into_response(self) -> Response {
+                    Response::from(self)
+                }
+            }
+        )*
+    };
+}
+
+error_responses!(FieldError, FormError, JsonError, MultipartError);
@@ -0,0 +1,188 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidHeader {
+    Name(String),
+    Value(String),
+}
+
+impl fmt::Display for InvalidHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidHeader::Name(name) => write!(f, "invalid header name: {name:?}"),
+            InvalidHeader::Value(name) => write!(f, "invalid value for header {name:?}"),
+        }
+    }
+}
+
+impl Error for InvalidHeader {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        HeaderMap {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn insert(&mut self, name: &str, value: &str) -> Result<(), InvalidHeader> {
+        Self::validate(name, value)?;
+
+        match self.entries.iter().position(|(key, _)| key.eq_ignore_ascii_case(name)) {
+            Some(index) => {
+                self.entries[index] = (name.to_string(), value.to_string());
+                let mut position = 0;
+                self.entries.retain(|(key, _)| {
+                    let keep = position <= index || !key.eq_ignore_ascii_case(name);
+                    position += 1;
+                    keep
+                });
+            }
+            None => self.entries.push((name.to_string(), value.to_string())),
+        }
+
+        Ok(())
+    }
+
+    pub fn append(&mut self, name: &str, value: &str) -> Result<(), InvalidHeader> {
+        Self::validate(name, value)?;
+        self.entries.push((name.to_string(), value.to_string()));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let removed = self.get(name).map(str::to_string);
+        self.entries.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        removed
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn validate(name: &str, value: &str) -> Result<(), InvalidHeader> {
+        if !is_token(name) {
+            return Err(InvalidHeader::Name(name.to_string()));
+        }
+
+        if value.bytes().any(|byte| matches!(byte, b'\r' | b'\n' | b'\0')) {
+            return Err(InvalidHeader::Value(name.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(headers: &HeaderMap) -> Vec<(&str, &str)> {
+        headers.iter().collect()
+    }
+
+    #[test]
+    fn lookups_ignore_case() {
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Type", "text/plain").unwrap();
+
+        assert_eq!(headers.get("content-type"), Some("text/plain"));
+        assert_eq!(headers.get("CONTENT-TYPE"), Some("text/plain"));
+        assert!(headers.contains("Content-type"));
+        assert_eq!(headers.get("Content-Length"), None);
+    }
+
+    #[test]
+    fn insert_replaces_all_values_in_place() {
+        let mut headers = HeaderMap::new();
+        headers.append("A", "1").unwrap();
+        headers.append("Set-Cookie", "a=1").unwrap();
+        headers.append("B", "2").unwrap();
+        headers.append("set-cookie", "b=2").unwrap();
+        headers.append("SET-COOKIE", "c=3").unwrap();
+
+        headers.insert("Set-Cookie", "d=4").unwrap();
+        assert_eq!(entries(&headers), vec![("A", "1"), ("Set-Cookie", "d=4"), ("B", "2")]);
+
+        headers.insert("c", "3").unwrap();
+        headers.insert("a", "one").unwrap();
+        assert_eq!(entries(&headers), vec![("a", "one"), ("Set-Cookie", "d=4"), ("B", "2"), ("c", "3")]);
+    }
+
+    #[test]
+    fn append_keeps_every_value_in_order() {
+        let mut headers = HeaderMap::new();
+        headers.append("Vary", "Accept").unwrap();
+        headers.append("X-Other", "x").unwrap();
+        headers.append("vary", "Cookie").unwrap();
+
+        assert_eq!(headers.get("Vary"), Some("Accept"));
+        assert_eq!(headers.get_all("VARY"), vec!["Accept", "Cookie"]);
+        assert_eq!(headers.len(), 3);
+    }
+
+    #[test]
+    fn remove_drops_every_value() {
+        let mut headers = HeaderMap::new();
+        headers.append("Vary", "Accept").unwrap();
+        headers.append("vary", "Cookie").unwrap();
+
+        assert_eq!(headers.remove("VARY"), Some("Accept".to_string()));
+        assert!(headers.is_empty());
+        assert_eq!(headers.remove("Vary"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_names_and_values() {
+        let mut headers = HeaderMap::new();
+        for name in ["", "Bad Name", "Bad:Name", "Bad\r\nName", "Caf\u{e9}", "(x)"] {
+            assert_eq!(headers.insert(name, "value"), Err(InvalidHeader::Name(name.to_string())));
+        }
+        for value in ["a\r\nInjected: 1", "a\nb", "a\rb", "a\0b"] {
+            assert_eq!(headers.append("X-Test", value), Err(InvalidHeader::Value("X-Test".to_string())));
+        }
+
+        assert!(headers.insert("X-Custom_Header.v1!#$%&'*+^`|~", "tab\tand unicode \u{e9}").is_ok());
+        assert!(headers.insert("X-Empty", "").is_ok());
+        assert_eq!(headers.len(), 2);
+    }
+}
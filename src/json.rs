@@ -0,0 +1,738 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+use crate::response::Response;
+
+const MAX_DEPTH: usize = 128;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(input: &str) -> Result<Json, JsonError> {
+        let mut parser = Parser { input: input.as_bytes(), position: 0 };
+        parser.skip_whitespace();
+        let value = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.position < parser.input.len() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+
+    pub fn object() -> Json {
+        Json::Object(Vec::new())
+    }
+
+    pub fn insert(&mut self, key: &str, value: impl ToJson) {
+        if let Json::Object(entries) = self {
+            let value = value.to_json();
+            match entries.iter_mut().find(|(existing, _)| existing == key) {
+                Some((_, existing)) => *existing = value,
+                None => entries.push((key.to_string(), value)),
+            }
+        }
+    }
+
+    pub fn with(mut self, key: &str, value: impl ToJson) -> Json {
+        self.insert(key, value);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(existing, _)| existing == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn field<T: FromJson>(&self, key: &str) -> Result<T, JsonError> {
+        if !matches!(self, Json::Object(_)) {
+            return Err(JsonError::invalid("expected an object"));
+        }
+        T::from_json(self.get(key).unwrap_or(&Json::Null)).map_err(|e| e.within(key))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_f64()
+            .filter(|value| value.fract() == 0.0 && value.abs() <= 9_007_199_254_740_992.0)
+            .map(|value| value as i64)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self {
+            Json::Object(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) if !value.is_finite() => f.write_str("null"),
+            Json::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => write!(f, "{}", *value as i64),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(entries) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for ch in value.chars() {
+        match ch {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\u{08}' => f.write_str("\\b")?,
+            '\u{0C}' => f.write_str("\\f")?,
+            '\u{00}'..='\u{1F}' | '\u{2028}' | '\u{2029}' => write!(f, "\\u{:04x}", ch as u32)?,
+            _ => f.write_char(ch)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonError {
+    UnsupportedMediaType,
+    Syntax { message: String, line: usize, column: usize },
+    Invalid { path: String, message: String },
+}
+
+impl JsonError {
+    pub fn invalid(message: &str) -> Self {
+        JsonError::Invalid {
+            path: String::new(),
+            message: message.to_string(),
+        }
+    }
+
+    fn within(self, segment: &str) -> Self {
+        match self {
+            JsonError::Invalid { path, message } => {
+                let path = match path.as_str() {
+                    "" => segment.to_string(),
+                    _ if path.starts_with('[') => format!("{segment}{path}"),
+                    _ => format!("{segment}.{path}"),
+                };
+                JsonError::Invalid { path, message }
+            }
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::UnsupportedMediaType => write!(f, "expected an application/json body"),
+            JsonError::Syntax { message, line, column } => write!(f, "{message} at line {line} column {column}"),
+            JsonError::Invalid { path, message } if path.is_empty() => write!(f, "{message}"),
+            JsonError::Invalid { path, message } => write!(f, "{path}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<JsonError> for Response {
+    fn from(error: JsonError) -> Self {
+        let status = match error {
+            JsonError::UnsupportedMediaType => 415,
+            JsonError::Syntax { .. } => 400,
+            JsonError::Invalid { .. } => 422,
+        };
+        Response::json_value(status, &Json::object().with("error", error.to_string()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for JsonError {
+    fn from(error: serde_json::Error) -> Self {
+        let message = error.to_string();
+        let message = message.split(" at line ").next().unwrap_or_default();
+        match error.classify() {
+            serde_json::error::Category::Data => JsonError::invalid(message),
+            _ => JsonError::Syntax {
+                message: message.to_string(),
+                line: error.line(),
+                column: error.column(),
+            },
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        let consumed = &self.input[..self.position.min(self.input.len())];
+        let line = consumed.iter().filter(|byte| **byte == b'\n').count() + 1;
+        let line_start = consumed.iter().rposition(|byte| *byte == b'\n').map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&consumed[line_start..]).chars().count() + 1;
+        JsonError::Syntax {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str, value: Json) -> Result<Json, JsonError> {
+        if self.input[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Json, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'n') => self.expect("null", Json::Null),
+            Some(b't') => self.expect("true", Json::Bool(true)),
+            Some(b'f') => self.expect("false", Json::Bool(false)),
+            Some(b'"') => self.parse_string().map(Json::String),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'{') => self.parse_object(depth),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.position += 1;
+        let mut values = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            self.skip_whitespace();
+            values.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.position += 1;
+        let mut entries: Vec<(String, Json)> = Vec::new();
+        let mut keys = HashSet::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key_position = self.position;
+            let key = self.parse_string()?;
+            if !keys.insert(key.clone()) {
+                self.position = key_position;
+                return Err(self.error(&format!("duplicate key `{key}`")));
+            }
+
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected `:`"));
+            }
+            self.position += 1;
+            self.skip_whitespace();
+            entries.push((key, self.parse_value(depth + 1)?));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, JsonError> {
+        let start = self.position;
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+
+        match self.peek() {
+            Some(b'0') => self.position += 1,
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            if !self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                return Err(self.error("invalid number"));
+            }
+            self.skip_digits();
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.position += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if !self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                return Err(self.error("invalid number"));
+            }
+            self.skip_digits();
+        }
+
+        let text = std::str::from_utf8(&self.input[start..self.position]).map_err(|_| self.error("invalid number"))?;
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Json::Number(value)),
+            _ => Err(self.error("number out of range")),
+        }
+    }
+
+    fn skip_digits(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.position += 1;
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.position += 1;
+                    return String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"));
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{08}',
+                        Some(b'f') => '\u{0C}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    self.position += 1;
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(0x00..=0x1F) => return Err(self.error("control character in string")),
+                Some(byte) => {
+                    bytes.push(byte);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.parse_hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+
+        if !self.input[self.position + 1..].starts_with(b"\\u") {
+            return Err(self.error("unpaired surrogate"));
+        }
+        self.position += 2;
+        let low = self.parse_hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("unpaired surrogate"));
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .input
+            .get(self.position + 1..self.position + 5)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let value = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        Ok(value)
+    }
+}
+
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+pub trait FromJson: Sized {
+    fn from_json(json: &Json) -> Result<Self, JsonError>;
+}
+
+impl ToJson for Json {
+    fn to_json(&self) -> Json {
+        self.clone()
+    }
+}
+
+impl FromJson for Json {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        Ok(json.clone())
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Json {
+        Json::Bool(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        json.as_bool().ok_or_else(|| mismatch("a boolean", json))
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Json {
+        Json::String(self.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        json.as_str().map(str::to_string).ok_or_else(|| mismatch("a string", json))
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> Json {
+        Json::Number(*self)
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        json.as_f64().ok_or_else(|| mismatch("a number", json))
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json(&self) -> Json {
+        Json::Number(f64::from(*self))
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        json.as_f64().map(|value| value as f32).ok_or_else(|| mismatch("a number", json))
+    }
+}
+
+macro_rules! json_integers {
+    ($($integer:ty),*) => {
+        $(
+            impl ToJson for $integer {
+                fn to_json(&self) -> Json {
+                    Json::Number(*self as f64)
+                }
+            }
+
+            impl FromJson for $integer {
+                fn from_json(json: &Json) -> Result<Self, JsonError> {
+                    json.as_i64()
+                        .and_then(|value| <$integer>::try_from(value).ok())
+                        .ok_or_else(|| mismatch(concat!("an integer in the range of ", stringify!($integer)), json))
+                }
+            }
+        )*
+    };
+}
+
+json_integers!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Json {
+        match self {
+            Some(value) => value.to_json(),
+            None => Json::Null,
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        match json {
+            Json::Null => Ok(None),
+            _ => T::from_json(json).map(Some),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Json {
+        Json::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Json {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        json.as_array()
+            .ok_or_else(|| mismatch("an array", json))?
+            .iter()
+            .enumerate()
+            .map(|(i, value)| T::from_json(value).map_err(|e| e.within(&format!("[{i}]"))))
+            .collect()
+    }
+}
+
+impl<T: ToJson> ToJson for HashMap<String, T> {
+    fn to_json(&self) -> Json {
+        let mut entries: Vec<(String, Json)> = self.iter().map(|(key, value)| (key.clone(), value.to_json())).collect();
+        entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+        Json::Object(entries)
+    }
+}
+
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        json.as_object()
+            .ok_or_else(|| mismatch("an object", json))?
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), T::from_json(value).map_err(|e| e.within(key))?)))
+            .collect()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+fn mismatch(expected: &str, found: &Json) -> JsonError {
+    JsonError::invalid(&format!("expected {expected}, found {}", found.kind()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_error(input: &str) -> (String, usize, usize) {
+        match Json::parse(input) {
+            Err(JsonError::Syntax { message, line, column }) => (message, line, column),
+            other => panic!("expected a syntax error for {input:?}, got {other:?}"),
+        }
+    }
+
+    fn nested(depth: usize) -> String {
+        format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn parses_values() {
+        let json = Json::parse(" {\"a\": [1, -0.5e2, 1E3, 0, true, false, null], \"b\": {\"c\": \"d\"}, \"\": {}} ").unwrap();
+
+        let numbers: Vec<f64> = json.get("a").unwrap().as_array().unwrap()[..4].iter().filter_map(Json::as_f64).collect();
+        assert_eq!(numbers, vec![1.0, -50.0, 1000.0, 0.0]);
+        assert_eq!(json.get("b").and_then(|b| b.get("c")).and_then(Json::as_str), Some("d"));
+        assert_eq!(json.get(""), Some(&Json::object()));
+        assert_eq!(Json::parse("[]"), Ok(Json::Array(Vec::new())));
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_position() {
+        assert_eq!(syntax_error("[1,\n  ]"), ("unexpected character".to_string(), 2, 3));
+        assert_eq!(syntax_error("{\"a\" 1}").0, "expected `:`");
+        assert_eq!(syntax_error("1 2").0, "unexpected trailing characters");
+        assert_eq!(syntax_error("\"abc").0, "unterminated string");
+        assert_eq!(syntax_error("\"a\tb\"").0, "control character in string");
+        assert_eq!(syntax_error("\"\\x\"").0, "invalid escape");
+        assert_eq!(syntax_error("1e400").0, "number out of range");
+        for input in ["", "01", "1.", "-", "1e", ".5", "tru", "nul", "[1,]", "{,}", "{\"a\":1,}", "'a'"] {
+            syntax_error(input);
+        }
+    }
+
+    #[test]
+    fn decodes_escapes_and_surrogate_pairs() {
+        let json = Json::parse(r#""\"\\\/\b\f\n\r\t \u00e9 \ud83d\ude00 \u2028""#).unwrap();
+        assert_eq!(json.as_str(), Some("\"\\/\u{08}\u{0C}\n\r\t \u{e9} \u{1F600} \u{2028}"));
+
+        assert_eq!(syntax_error(r#""\ud83d""#).0, "unpaired surrogate");
+        assert_eq!(syntax_error(r#""\ud83d\u0041""#).0, "unpaired surrogate");
+        assert_eq!(syntax_error(r#""\ud83d\n""#).0, "unpaired surrogate");
+        assert_eq!(syntax_error(r#""\ude00""#).0, "invalid unicode escape");
+        assert_eq!(syntax_error(r#""\ud83d"#).0, "unpaired surrogate");
+        assert_eq!(syntax_error(r#""\u12"#).0, "invalid unicode escape");
+        assert_eq!(syntax_error(r#""\u+123""#).0, "invalid unicode escape");
+    }
+
+    #[test]
+    fn rejects_duplicate_keys() {
+        assert_eq!(syntax_error("{\"a\": 1, \"b\": 2, \"a\": 3}"), ("duplicate key `a`".to_string(), 1, 18));
+        assert_eq!(syntax_error("{\"\\u0061\": 1, \"a\": 2}").0, "duplicate key `a`");
+        assert!(Json::parse("{\"a\": {\"a\": 1}, \"b\": [{\"a\": 1}, {\"a\": 2}]}").is_ok());
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_ok());
+        assert_eq!(syntax_error(&nested(MAX_DEPTH + 2)).0, "nesting too deep");
+        assert_eq!(syntax_error(&"{\"a\":".repeat(100_000)).0, "nesting too deep");
+    }
+
+    #[test]
+    fn escapes_strings_when_serializing() {
+        let value = "quote \" backslash \\ newline \n tab \t nul \u{0} bell \u{7} separator \u{2028} caf\u{e9} \u{1F600}";
+        let serialized = Json::String(value.to_string()).to_string();
+
+        assert_eq!(
+            serialized,
+            "\"quote \\\" backslash \\\\ newline \\n tab \\t nul \\u0000 bell \\u0007 separator \\u2028 caf\u{e9} \u{1F600}\""
+        );
+        assert_eq!(Json::parse(&serialized).unwrap().as_str(), Some(value));
+    }
+
+    #[test]
+    fn serializes_numbers_and_containers() {
+        let json = Json::object()
+            .with("int", 42)
+            .with("float", 1.5)
+            .with("nan", f64::NAN)
+            .with("list", vec![Some(1), None])
+            .with("nested", Json::object().with("key", "value"));
+
+        assert_eq!(json.to_string(), r#"{"int":42,"float":1.5,"nan":null,"list":[1,null],"nested":{"key":"value"}}"#);
+        assert_eq!(Json::parse(&json.to_string()).unwrap().get("list"), json.get("list"));
+    }
+
+    #[test]
+    fn reports_paths_for_invalid_values() {
+        let json = Json::parse(r#"[{"a": 1}, {"a": 300}]"#).unwrap();
+        let error = Vec::<HashMap<String, u8>>::from_json(&json).unwrap_err();
+
+        assert_eq!(error.to_string(), "[1].a: expected an integer in the range of u8, found a number");
+    }
+}
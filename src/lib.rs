@@ -1,15 +1,35 @@
 pub mod logger;
+pub mod body;
+pub mod compress;
+pub mod cookie;
+mod crypto;
+pub mod date;
+pub mod extract;
+pub mod header;
+pub mod json;
+pub mod mime;
+pub mod multipart;
+pub mod params;
 pub mod router;
 pub mod template;
 pub mod request;
 pub mod response;
+pub mod session;
+pub mod static_files;
+pub mod status;
+pub mod url;
+mod conditional;
+mod range;
 
 use std::net::{TcpListener, TcpStream};
-use std::io::{prelude::*, BufReader};
-use std::sync::Arc;
+use std::io::{prelude::*, BufReader, BufWriter};
+use std::sync::{Arc, Mutex};
+use header::HeaderMap;
+use multipart::{MultipartConfig, Upload};
 use router::Router;
 use request::Request;
 use response::Response;
+use status::StatusCode;
 
 #[macro_export]
 macro_rules! listen {
@@ -54,49 +74,81 @@ pub fn listen_with_router(address: &str, port: u16, log: bool, router: Router) {
 }
 
 fn handle_connection(mut stream: TcpStream, router: Arc<Router>) {
-    let buf_reader = BufReader::new(&mut stream);
-    let mut lines = buf_reader.lines();
+    let mut buf_reader = BufReader::new(&mut stream);
 
-    let request_line = lines.next().unwrap().unwrap();
-    let mut headers = std::collections::HashMap::new();
-    let body = String::new();
+    let request_line = read_line(&mut buf_reader).unwrap_or_default();
+    let mut headers = HeaderMap::new();
 
-    for line in lines {
-        let line = line.unwrap();
+    while let Some(line) = read_line(&mut buf_reader) {
         if line.is_empty() {
             break;
         }
-        if let Some((key, value)) = line.split_once(": ") {
-            headers.insert(key.to_string(), value.to_string());
+        if let Some((key, value)) = line.split_once(':') {
+            let _ = headers.append(key, value.trim());
         }
     }
 
+    let content_length = headers
+        .get("Content-Length")
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(0);
+
     let parts: Vec<&str> = request_line.split(' ').collect();
-    if parts.len() >= 2 {
-        let method = parts[0];
-        let path = parts[1];
-
-        let request = Request::new(method, path, headers, body);
-        let response = router.handle_request(&request);
-
-        let response_string = format!(
-            "HTTP/1.1 {} {}\r\n{}\r\n\r\n{}",
-            response.status_code,
-            response.status_text,
-            response.headers_string(),
-            response.body
-        );
-
-        stream.write_all(response_string.as_bytes()).unwrap();
+    let head_only = parts.first() == Some(&"HEAD");
+    let multipart = router.streamed_multipart(&headers);
+    let limit = multipart.map_or(router.body_limit(), MultipartConfig::total_limit);
+
+    let mut response = if content_length > limit {
+        Response::with_status(StatusCode::CONTENT_TOO_LARGE, "Request body too large")
+    } else if parts.len() < 2 {
+        Response::with_status(StatusCode::BAD_REQUEST, "Invalid request format")
+    } else if let Some(config) = multipart {
+        let content_type = headers.get("Content-Type").map(str::to_string);
+        let upload = config.read(content_type.as_deref(), (&mut buf_reader).take(content_length));
+
+        let mut request = Request::new(parts[0], parts[1], headers, Vec::new());
+        request.upload = Mutex::new(Upload::Streamed(config.clone(), upload));
+        router.handle_request(&mut request)
     } else {
-        let error_response = Response::new(400, "Bad Request", "Invalid request format");
-        let response_string = format!(
-            "HTTP/1.1 {} {}\r\n{}\r\n\r\n{}",
-            error_response.status_code,
-            error_response.status_text,
-            error_response.headers_string(),
-            error_response.body
-        );
-        stream.write_all(response_string.as_bytes()).unwrap();
+        let mut body = Vec::new();
+        let body_read = (&mut buf_reader)
+            .take(content_length)
+            .read_to_end(&mut body)
+            .is_ok_and(|read| read as u64 == content_length);
+
+        if body_read {
+            let mut request = Request::new(parts[0], parts[1], headers, body);
+            router.handle_request(&mut request)
+        } else {
+            Response::with_status(StatusCode::BAD_REQUEST, "Invalid request format")
+        }
+    };
+
+    response.set_header("Connection", "close");
+
+    let mut writer = BufWriter::new(&stream);
+    let result = response.write_head(&mut writer).and_then(|_| {
+        if head_only || !response.allows_body() {
+            writer.flush()
+        } else {
+            response.body.send(&stream, &mut writer)
+        }
+    });
+
+    if let Err(e) = result {
+        error!("Failed to write response: {e}");
     }
-}
\ No newline at end of file
+}
+
+fn read_line(reader: &mut impl BufRead) -> Option<String> {
+    let mut line = Vec::new();
+    match reader.read_until(b'\n', &mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => {
+            while line.last().is_some_and(|byte| *byte == b'\n' || *byte == b'\r') {
+                line.pop();
+            }
+            Some(String::from_utf8_lossy(&line).into_owned())
+        }
+    }
+}
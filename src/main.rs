@@ -1,4 +1,5 @@
 use syne::{listen_with_router, route};
+use syne::json::{FromJson, Json, JsonError, ToJson};
 use syne::router::Router;
 use syne::request::Request;
 use syne::response::Response;
@@ -22,6 +23,43 @@ struct Post {
     author: String,
 }
 
+impl ToJson for User {
+    fn to_json(&self) -> Json {
+        Json::object()
+            .with("id", self.id)
+            .with("name", &self.name)
+            .with("email", &self.email)
+            .with("age", self.age)
+    }
+}
+
+impl ToJson for Post {
+    fn to_json(&self) -> Json {
+        let preview = self.content.chars().take(100).collect::<String>() + "...";
+        Json::object()
+            .with("id", self.id)
+            .with("title", &self.title)
+            .with("author", &self.author)
+            .with("content", preview)
+    }
+}
+
+struct NewUser {
+    name: String,
+    email: String,
+    age: u32,
+}
+
+impl FromJson for NewUser {
+    fn from_json(json: &Json) -> Result<Self, JsonError> {
+        Ok(NewUser {
+            name: json.field("name")?,
+            email: json.field("email")?,
+            age: json.field("age")?,
+        })
+    }
+}
+
 struct AppState {
     template_engine: TemplateEngine,
     users: Vec<User>,
@@ -208,43 +246,37 @@ fn about_handler(_req: &Request, app_state: Arc<Mutex<AppState>>) -> Response {
 fn api_users_handler(_req: &Request, app_state: Arc<Mutex<AppState>>) -> Response {
     let state = app_state.lock().unwrap();
 
-    let users_json: Vec<String> = state.users.iter().map(|user| {
-        format!(
-            r#"{{"id": {}, "name": "{}", "email": "{}", "age": {}}}"#,
-            user.id, user.name, user.email, user.age
-        )
-    }).collect();
-
-    let json_response = format!(
-        r#"{{"users": [{}], "total": {}}}"#,
-        users_json.join(","),
-        state.users.len()
-    );
+    let body = Json::object()
+        .with("users", &state.users)
+        .with("total", state.users.len());
 
-    Response::json(200, &json_response)
+    Response::json_value(200, &body)
 }
 
 fn api_posts_handler(_req: &Request, app_state: Arc<Mutex<AppState>>) -> Response {
     let state = app_state.lock().unwrap();
 
-    let posts_json: Vec<String> = state.posts.iter().map(|post| {
-        format!(
-            r#"{{"id": {}, "title": "{}", "author": "{}", "content": "{}"}}"#,
-            post.id, post.title, post.author,
-            post.content.chars().take(100).collect::<String>() + "..."
-        )
-    }).collect();
+    let body = Json::object()
+        .with("posts", &state.posts)
+        .with("total", state.posts.len());
 
-    let json_response = format!(
-        r#"{{"posts": [{}], "total": {}}}"#,
-        posts_json.join(","),
-        state.posts.len()
-    );
-
-    Response::json(200, &json_response)
+    Response::json_value(200, &body)
 }
 
-fn api_create_user_handler(_req: &Request, _app_state: Arc<Mutex<AppState>>) -> Response {
-    let response = r#"{"message": "User created successfully", "id": 6}"#;
-    Response::json(201, response)
+fn api_create_user_handler(req: &Request, app_state: Arc<Mutex<AppState>>) -> Response {
+    let new_user: NewUser = match req.json() {
+        Ok(new_user) => new_user,
+        Err(e) => return e.into(),
+    };
+
+    let mut state = app_state.lock().unwrap();
+    let id = state.users.iter().map(|user| user.id).max().unwrap_or(0) + 1;
+    let user = User { id, name: new_user.name, email: new_user.email, age: new_user.age };
+    state.users.push(user.clone());
+
+    let body = Json::object()
+        .with("message", "User created successfully")
+        .with("user", &user);
+
+    Response::json_value(201, &body)
 }
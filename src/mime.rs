@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const DEFAULT: &str = "application/octet-stream";
+
+const BUILT_IN: &[(&str, &str)] = &[
+    ("aac", "audio/aac"),
+    ("apng", "image/apng"),
+    ("atom", "application/atom+xml"),
+    ("avi", "video/x-msvideo"),
+    ("avif", "image/avif"),
+    ("bin", "application/octet-stream"),
+    ("bmp", "image/bmp"),
+    ("bz2", "application/x-bzip2"),
+    ("cjs", "text/javascript"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("doc", "application/msword"),
+    ("docx", "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+    ("eot", "application/vnd.ms-fontobject"),
+    ("epub", "application/epub+zip"),
+    ("flac", "audio/flac"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("ics", "text/calendar"),
+    ("jar", "application/java-archive"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("m4a", "audio/mp4"),
+    ("manifest", "application/manifest+json"),
+    ("map", "application/json"),
+    ("md", "text/markdown"),
+    ("mid", "audio/midi"),
+    ("midi", "audio/midi"),
+    ("mjs", "text/javascript"),
+    ("mkv", "video/x-matroska"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("mpeg", "video/mpeg"),
+    ("oga", "audio/ogg"),
+    ("ogg", "audio/ogg"),
+    ("ogv", "video/ogg"),
+    ("opus", "audio/opus"),
+    ("otf", "font/otf"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    ("pptx", "application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+    ("rar", "application/vnd.rar"),
+    ("rss", "application/rss+xml"),
+    ("rtf", "application/rtf"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("toml", "application/toml"),
+    ("ts", "video/mp2t"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain"),
+    ("vtt", "text/vtt"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("weba", "audio/webm"),
+    ("webm", "video/webm"),
+    ("webmanifest", "application/manifest+json"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xhtml", "application/xhtml+xml"),
+    ("xls", "application/vnd.ms-excel"),
+    ("xlsx", "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("zip", "application/zip"),
+    ("7z", "application/x-7z-compressed"),
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct MimeTypes {
+    custom: HashMap<String, String>,
+}
+
+impl MimeTypes {
+    pub fn new() -> Self {
+        MimeTypes {
+            custom: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, extension: &str, content_type: &str) {
+        self.custom.insert(normalize_extension(extension), content_type.to_string());
+    }
+
+    pub fn lookup(&self, extension: &str) -> String {
+        let extension = normalize_extension(extension);
+        match self.custom.get(&extension) {
+            Some(content_type) => with_charset(content_type),
+            None => from_extension(&extension),
+        }
+    }
+
+    pub fn for_path(&self, path: &Path) -> String {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(extension) => self.lookup(extension),
+            None => DEFAULT.to_string(),
+        }
+    }
+}
+
+pub fn from_extension(extension: &str) -> String {
+    let extension = normalize_extension(extension);
+    BUILT_IN
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|(_, content_type)| with_charset(content_type))
+        .unwrap_or_else(|| DEFAULT.to_string())
+}
+
+pub fn from_path(path: &Path) -> String {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(extension) => from_extension(extension),
+        None => DEFAULT.to_string(),
+    }
+}
+
+pub fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+pub fn is_text(content_type: &str) -> bool {
+    let essence = essence(content_type);
+
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence.as_str(),
+            "application/json" | "application/javascript" | "application/xml" | "application/toml" | "application/yaml"
+        )
+}
+
+fn with_charset(content_type: &str) -> String {
+    if is_text(content_type) && !content_type.contains("charset=") {
+        format!("{content_type}; charset=utf-8")
+    } else {
+        content_type.to_string()
+    }
+}
+
+fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_ascii_lowercase()
+}
@@ -0,0 +1,550 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use crate::crypto::{base64_url_encode, random_bytes};
+use crate::mime;
+use crate::params::ParamMap;
+use crate::response::Response;
+
+const MULTIPART_FORM_DATA: &str = "multipart/form-data";
+const READ_CHUNK: usize = 64 * 1024;
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_PART_HEADERS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultipartConfig {
+    max_file_size: usize,
+    max_total_size: usize,
+    spill: Option<(PathBuf, usize)>,
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultipartConfig {
+    pub fn new() -> Self {
+        MultipartConfig {
+            max_file_size: 8 * 1024 * 1024,
+            max_total_size: 32 * 1024 * 1024,
+            spill: None,
+        }
+    }
+
+    pub fn max_file_size(mut self, bytes: usize) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    pub fn max_total_size(mut self, bytes: usize) -> Self {
+        self.max_total_size = bytes;
+        self
+    }
+
+    pub fn spill_to(mut self, directory: impl Into<PathBuf>, threshold: usize) -> Self {
+        self.spill = Some((directory.into(), threshold));
+        self
+    }
+
+    pub(crate) fn total_limit(&self) -> u64 {
+        self.max_total_size as u64
+    }
+
+    pub(crate) fn read(&self, content_type: Option<&str>, reader: impl BufRead) -> Result<Multipart, MultipartError> {
+        let content_type = content_type.ok_or(MultipartError::UnsupportedMediaType)?;
+        if !is_multipart(content_type) {
+            return Err(MultipartError::UnsupportedMediaType);
+        }
+        let boundary = header_parameter(content_type, "boundary").ok_or(MultipartError::MissingBoundary)?;
+
+        let delimiter = format!("\r\n--{boundary}").into_bytes();
+        let mut stream = PartStream {
+            reader,
+            buffer: b"\r\n".to_vec(),
+            read: 0,
+            limit: self.max_total_size,
+        };
+        let mut multipart = Multipart {
+            fields: ParamMap::new(),
+            files: Vec::new(),
+        };
+
+        stream.copy_until(&delimiter, |_| Ok(()))?;
+        loop {
+            if stream.starts_with(b"--")? {
+                return Ok(multipart);
+            }
+            let line = stream.read_line()?;
+            if line.iter().any(|byte| !matches!(byte, b' ' | b'\t')) {
+                return Err(MultipartError::Malformed);
+            }
+
+            let mut headers = Vec::new();
+            loop {
+                let line = stream.read_line()?;
+                if line.is_empty() {
+                    break;
+                }
+                if headers.len() == MAX_PART_HEADERS {
+                    return Err(MultipartError::Malformed);
+                }
+                headers.push(String::from_utf8(line).map_err(|_| MultipartError::Malformed)?);
+            }
+
+            self.read_part(&mut multipart, &headers, &mut stream, &delimiter)?;
+        }
+    }
+
+    fn read_part(
+        &self,
+        multipart: &mut Multipart,
+        headers: &[String],
+        stream: &mut PartStream<impl BufRead>,
+        delimiter: &[u8],
+    ) -> Result<(), MultipartError> {
+        let mut disposition = None;
+        let mut content_type = None;
+        for line in headers {
+            match line.split_once(':') {
+                Some((name, value)) if name.trim().eq_ignore_ascii_case("Content-Disposition") => disposition = Some(value.trim()),
+                Some((name, value)) if name.trim().eq_ignore_ascii_case("Content-Type") => content_type = Some(value.trim()),
+                _ => {}
+            }
+        }
+
+        let disposition = disposition.ok_or(MultipartError::Malformed)?;
+        let name = header_parameter(disposition, "name").ok_or(MultipartError::Malformed)?;
+
+        let Some(filename) = header_parameter(disposition, "filename") else {
+            let mut value = Vec::new();
+            stream.copy_until(delimiter, |chunk| {
+                value.extend_from_slice(chunk);
+                Ok(())
+            })?;
+            let value = String::from_utf8(value).map_err(|_| MultipartError::Malformed)?;
+            multipart.fields.insert(&name, &value);
+            return Ok(());
+        };
+
+        let mut file = FilePart {
+            name,
+            filename,
+            content_type: content_type.unwrap_or("text/plain").to_string(),
+            size: 0,
+            data: FileData::Memory(Vec::new()),
+        };
+        let mut spill_file = None;
+        stream.copy_until(delimiter, |chunk| {
+            file.size += chunk.len() as u64;
+            if file.size > self.max_file_size as u64 {
+                return Err(MultipartError::FileTooLarge(file.name.clone()));
+            }
+
+            if let FileData::Memory(bytes) = &mut file.data
+                && let Some((directory, threshold)) = &self.spill
+                && file.size > *threshold as u64
+            {
+                let (path, mut handle) = create_spill_file(directory)?;
+                let buffered = std::mem::take(bytes);
+                file.data = FileData::Disk(path);
+                handle.write_all(&buffered)?;
+                spill_file = Some(handle);
+            }
+
+            match (&mut file.data, &mut spill_file) {
+                (FileData::Disk(_), Some(handle)) => handle.write_all(chunk)?,
+                (FileData::Memory(bytes), _) => bytes.extend_from_slice(chunk),
+                (FileData::Disk(_), None) => unreachable!("spilled parts always have an open file"),
+            }
+            Ok(())
+        })?;
+
+        if let Some(mut handle) = spill_file {
+            handle.flush()?;
+        }
+        multipart.files.push(file);
+        Ok(())
+    }
+}
+
+struct PartStream<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    read: usize,
+    limit: usize,
+}
+
+impl<R: BufRead> PartStream<R> {
+    fn fill(&mut self) -> Result<bool, MultipartError> {
+        let available = self.reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(false);
+        }
+
+        let count = available.len().min(READ_CHUNK);
+        self.read += count;
+        if self.read > self.limit {
+            return Err(MultipartError::PayloadTooLarge);
+        }
+        self.buffer.extend_from_slice(&available[..count]);
+        self.reader.consume(count);
+        Ok(true)
+    }
+
+    fn starts_with(&mut self, prefix: &[u8]) -> Result<bool, MultipartError> {
+        while self.buffer.len() < prefix.len() && self.fill()? {}
+        Ok(self.buffer.starts_with(prefix))
+    }
+
+    fn read_line(&mut self) -> Result<Vec<u8>, MultipartError> {
+        loop {
+            if let Some(end) = find(&self.buffer, b"\r\n") {
+                let mut line: Vec<u8> = self.buffer.drain(..end + 2).collect();
+                line.truncate(end);
+                return Ok(line);
+            }
+            if self.buffer.len() > MAX_LINE_LENGTH || !self.fill()? {
+                return Err(MultipartError::Malformed);
+            }
+        }
+    }
+
+    fn copy_until(
+        &mut self,
+        delimiter: &[u8],
+        mut write: impl FnMut(&[u8]) -> Result<(), MultipartError>,
+    ) -> Result<(), MultipartError> {
+        loop {
+            if let Some(index) = find(&self.buffer, delimiter) {
+                write(&self.buffer[..index])?;
+                self.buffer.drain(..index + delimiter.len());
+                return Ok(());
+            }
+
+            let keep = delimiter.len() - 1;
+            if self.buffer.len() > keep {
+                let flushed = self.buffer.len() - keep;
+                write(&self.buffer[..flushed])?;
+                self.buffer.drain(..flushed);
+            }
+            if !self.fill()? {
+                return Err(MultipartError::Malformed);
+            }
+        }
+    }
+}
+
+pub(crate) enum Upload {
+    Buffered,
+    Streamed(MultipartConfig, Result<Multipart, MultipartError>),
+    Read(MultipartConfig),
+}
+
+#[derive(Debug)]
+pub struct Multipart {
+    fields: ParamMap,
+    files: Vec<FilePart>,
+}
+
+impl Multipart {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name)
+    }
+
+    pub fn fields(&self) -> &ParamMap {
+        &self.fields
+    }
+
+    pub fn file(&self, name: &str) -> Option<&FilePart> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    pub fn files(&self) -> &[FilePart] {
+        &self.files
+    }
+
+    pub fn into_files(self) -> Vec<FilePart> {
+        self.files
+    }
+}
+
+#[derive(Debug)]
+enum FileData {
+    Memory(Vec<u8>),
+    Disk(PathBuf),
+}
+
+#[derive(Debug)]
+pub struct FilePart {
+    name: String,
+    filename: String,
+    content_type: String,
+    size: u64,
+    data: FileData,
+}
+
+impl FilePart {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match &self.data {
+            FileData::Memory(_) => None,
+            FileData::Disk(path) => Some(path),
+        }
+    }
+
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        match &self.data {
+            FileData::Memory(bytes) => Ok(bytes.clone()),
+            FileData::Disk(path) => fs::read(path),
+        }
+    }
+
+    pub fn open(&self) -> io::Result<Box<dyn io::Read + Send>> {
+        match &self.data {
+            FileData::Memory(bytes) => Ok(Box::new(io::Cursor::new(bytes.clone()))),
+            FileData::Disk(path) => Ok(Box::new(File::open(path)?)),
+        }
+    }
+
+    pub fn persist(self, destination: impl AsRef<Path>) -> io::Result<()> {
+        let destination = destination.as_ref();
+        match &self.data {
+            FileData::Memory(bytes) => fs::write(destination, bytes),
+            FileData::Disk(path) => fs::rename(path, destination).or_else(|_| fs::copy(path, destination).map(|_| ())),
+        }
+    }
+}
+
+impl Drop for FilePart {
+    fn drop(&mut self) {
+        if let FileData::Disk(path) = &self.data {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MultipartError {
+    UnsupportedMediaType,
+    MissingBoundary,
+    Malformed,
+    FileTooLarge(String),
+    PayloadTooLarge,
+    AlreadyRead,
+    ConfigMismatch,
+    Io(io::Error),
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultipartError::UnsupportedMediaType => write!(f, "expected a {MULTIPART_FORM_DATA} body"),
+            MultipartError::MissingBoundary => write!(f, "multipart body has no boundary"),
+            MultipartError::Malformed => write!(f, "malformed multipart body"),
+            MultipartError::FileTooLarge(name) => write!(f, "file `{name}` exceeds the size limit"),
+            MultipartError::PayloadTooLarge => write!(f, "multipart body exceeds the size limit"),
+            MultipartError::AlreadyRead => write!(f, "streamed multipart body was already read"),
+            MultipartError::ConfigMismatch => write!(f, "multipart config differs from the router's streaming config"),
+            MultipartError::Io(e) => write!(f, "failed to store upload: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for MultipartError {}
+
+impl From<io::Error> for MultipartError {
+    fn from(error: io::Error) -> Self {
+        MultipartError::Io(error)
+    }
+}
+
+impl From<MultipartError> for Response {
+    fn from(error: MultipartError) -> Self {
+        let status = match error {
+            MultipartError::UnsupportedMediaType => 415,
+            MultipartError::MissingBoundary | MultipartError::Malformed => 400,
+            MultipartError::FileTooLarge(_) | MultipartError::PayloadTooLarge => 413,
+            MultipartError::AlreadyRead | MultipartError::ConfigMismatch | MultipartError::Io(_) => 500,
+        };
+        Response::text(status, &error.to_string())
+    }
+}
+
+fn create_spill_file(directory: &Path) -> io::Result<(PathBuf, File)> {
+    fs::create_dir_all(directory)?;
+    let path = directory.join(format!("syne-upload-{}", base64_url_encode(&random_bytes(16))));
+    let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+    Ok((path, file))
+}
+
+pub(crate) fn is_multipart(content_type: &str) -> bool {
+    mime::essence(content_type) == MULTIPART_FORM_DATA
+}
+
+fn header_parameter(header: &str, name: &str) -> Option<String> {
+    let mut rest = header.split_once(';')?.1;
+
+    loop {
+        let (key, after_key) = rest.split_once('=')?;
+        let after_key = after_key.trim_start();
+
+        let (value, remaining) = match after_key.strip_prefix('"') {
+            Some(quoted) => {
+                let mut value = String::new();
+                let mut chars = quoted.char_indices();
+                let end = loop {
+                    match chars.next()? {
+                        (_, '\\') => value.push(chars.next()?.1),
+                        (index, '"') => break index + 1,
+                        (_, ch) => value.push(ch),
+                    }
+                };
+                let remaining = quoted[end..].split_once(';').map_or("", |(_, remaining)| remaining);
+                (value, remaining)
+            }
+            None => {
+                let (value, remaining) = after_key.split_once(';').unwrap_or((after_key, ""));
+                (value.trim().to_string(), remaining)
+            }
+        };
+
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+        rest = remaining;
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=\"XyZ\"";
+
+    fn body(parts: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+        let mut body = b"preamble to ignore\r\n".to_vec();
+        for (name, filename, content) in parts {
+            body.extend_from_slice(b"--XyZ\r\n");
+            match filename {
+                Some(filename) => body.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{name}\"; filename=\"{filename}\"\r\nContent-Type: image/png\r\n\r\n").as_bytes(),
+                ),
+                None => body.extend_from_slice(format!("Content-Disposition: form-data; name=\"{name}\"\r\n\r\n").as_bytes()),
+            }
+            body.extend_from_slice(content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"--XyZ--\r\nepilogue");
+        body
+    }
+
+    fn read(config: &MultipartConfig, body: &[u8], chunk: usize) -> Result<Multipart, MultipartError> {
+        config.read(Some(CONTENT_TYPE), BufReader::with_capacity(chunk, body))
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("syne-multipart-{}", base64_url_encode(&random_bytes(8))))
+    }
+
+    #[test]
+    fn parses_parts_split_at_every_read_size() {
+        let content: &[u8] = b"\x89PNG\r\n--XyY\r\n-XyZ\n--XyZ\r\n--Xy\r\n-";
+        let body = body(&[("title", None, "caf\u{e9}".as_bytes()), ("upload", Some("a \\\"b\\\".png"), content), ("tag", None, b"1"), ("tag", None, b"")]);
+
+        for chunk in 1..=body.len() {
+            let multipart = read(&MultipartConfig::new(), &body, chunk).unwrap_or_else(|e| panic!("chunk {chunk}: {e:?}"));
+            assert_eq!(multipart.field("title"), Some("caf\u{e9}"));
+            assert_eq!(multipart.fields().get_all("tag"), vec!["1", ""]);
+
+            let file = multipart.file("upload").unwrap();
+            assert_eq!(file.filename(), "a \"b\".png");
+            assert_eq!(file.content_type(), "image/png");
+            assert_eq!(file.size(), content.len() as u64);
+            assert_eq!(file.bytes().unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn accepts_a_body_ending_at_the_close_delimiter() {
+        let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--XyZ--";
+        for chunk in 1..=body.len() {
+            assert_eq!(read(&MultipartConfig::new(), body, chunk).unwrap().field("a"), Some("1"));
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_files_and_bodies() {
+        let body = body(&[("upload", Some("big.bin"), &[7; 1000])]);
+
+        let error = read(&MultipartConfig::new().max_file_size(999), &body, 64).unwrap_err();
+        assert!(matches!(error, MultipartError::FileTooLarge(name) if name == "upload"));
+        assert!(read(&MultipartConfig::new().max_file_size(1000), &body, 64).is_ok());
+
+        let error = read(&MultipartConfig::new().max_total_size(body.len() - 1), &body, 64).unwrap_err();
+        assert!(matches!(error, MultipartError::PayloadTooLarge));
+        assert!(read(&MultipartConfig::new().max_total_size(body.len()), &body, 64).is_ok());
+    }
+
+    #[test]
+    fn spills_large_files_and_cleans_up() {
+        let directory = temp_dir();
+        let config = MultipartConfig::new().spill_to(&directory, 100);
+        let body = body(&[("small", Some("a.txt"), &[1; 100]), ("large", Some("b.txt"), &[2; 5000])]);
+
+        let multipart = read(&config, &body, 7).unwrap();
+        assert!(multipart.file("small").unwrap().path().is_none());
+        let path = multipart.file("large").unwrap().path().unwrap().to_path_buf();
+        assert_eq!(fs::read(&path).unwrap(), vec![2; 5000]);
+
+        drop(multipart);
+        assert!(!path.exists());
+
+        let error = read(&config.max_file_size(1000), &body, 7).unwrap_err();
+        assert!(matches!(error, MultipartError::FileTooLarge(_)));
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 0);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_bodies() {
+        let config = MultipartConfig::new();
+        let complete = body(&[("a", None, b"1")]);
+        let truncated = &complete[..complete.len() - "--\r\nepilogue".len() - 4];
+
+        assert!(matches!(read(&config, truncated, 16), Err(MultipartError::Malformed)));
+        assert!(matches!(read(&config, b"no delimiter here", 16), Err(MultipartError::Malformed)));
+        assert!(matches!(read(&config, b"--XyZ\r\n\r\nvalue\r\n--XyZ--", 16), Err(MultipartError::Malformed)));
+        assert!(matches!(read(&config, &[b'-'; 20_000], 16), Err(MultipartError::Malformed)));
+        assert!(matches!(
+            config.read(Some("multipart/form-data"), complete.as_slice()),
+            Err(MultipartError::MissingBoundary)
+        ));
+        assert!(matches!(
+            config.read(Some("text/plain; boundary=XyZ"), complete.as_slice()),
+            Err(MultipartError::UnsupportedMediaType)
+        ));
+    }
+}
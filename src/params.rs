@@ -0,0 +1,212 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::response::Response;
+use crate::url::decode_query_lossy;
+
+pub(crate) const FORM_URLENCODED: &str = "application/x-www-form-urlencoded";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParamMap {
+    pairs: Vec<(String, String)>,
+}
+
+impl ParamMap {
+    pub fn new() -> Self {
+        ParamMap { pairs: Vec::new() }
+    }
+
+    pub fn parse(input: &str) -> Self {
+        let pairs = input
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => (decode_query_lossy(key), decode_query_lossy(value)),
+                None => (decode_query_lossy(pair), String::new()),
+            })
+            .collect();
+
+        ParamMap { pairs }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.pairs.iter().any(|(key, _)| key == name)
+    }
+
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.pairs.push((name.to_string(), value.to_string()));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn required<T>(&self, name: &str) -> Result<T, FieldError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.optional(name)?
+            .ok_or_else(|| FieldError::new(name, "missing value"))
+    }
+
+    pub fn optional<T>(&self, name: &str) -> Result<Option<T>, FieldError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.get(name).map(|value| parse_field(name, value)).transpose()
+    }
+
+    pub fn all<T>(&self, name: &str) -> Result<Vec<T>, FieldError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.get_all(name)
+            .into_iter()
+            .map(|value| parse_field(name, value))
+            .collect()
+    }
+
+    pub fn flag(&self, name: &str) -> Result<bool, FieldError> {
+        match self.get(name) {
+            None => Ok(false),
+            Some("" | "1" | "true" | "on" | "yes") => Ok(true),
+            Some("0" | "false" | "off" | "no") => Ok(false),
+            Some(value) => Err(FieldError::new(name, &format!("invalid boolean `{value}`"))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    field: String,
+    message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+impl From<FieldError> for Response {
+    fn from(error: FieldError) -> Self {
+        if error.field.is_empty() {
+            Response::text(400, &format!("Invalid parameters: {}", error.message))
+        } else {
+            Response::text(400, &format!("Invalid parameter `{}`: {}", error.field, error.message))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormError {
+    UnsupportedMediaType,
+    InvalidBody,
+    Field(FieldError),
+}
+
+impl fmt::Display for FormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormError::UnsupportedMediaType => write!(f, "expected an {FORM_URLENCODED} body"),
+            FormError::InvalidBody => write!(f, "form body is not valid UTF-8"),
+            FormError::Field(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FormError {}
+
+impl From<FieldError> for FormError {
+    fn from(error: FieldError) -> Self {
+        FormError::Field(error)
+    }
+}
+
+impl From<FormError> for Response {
+    fn from(error: FormError) -> Self {
+        match error {
+            FormError::UnsupportedMediaType => Response::text(415, &format!("Expected {FORM_URLENCODED}")),
+            FormError::InvalidBody => Response::text(400, "Invalid form body"),
+            FormError::Field(error) => error.into(),
+        }
+    }
+}
+
+pub trait FromQuery: Sized {
+    fn from_query(params: &ParamMap) -> Result<Self, FieldError>;
+}
+
+impl FromQuery for ParamMap {
+    fn from_query(params: &ParamMap) -> Result<Self, FieldError> {
+        Ok(params.clone())
+    }
+}
+
+pub trait FromForm: Sized {
+    fn from_form(params: &ParamMap) -> Result<Self, FieldError>;
+}
+
+impl FromForm for ParamMap {
+    fn from_form(params: &ParamMap) -> Result<Self, FieldError> {
+        Ok(params.clone())
+    }
+}
+
+fn parse_field<T>(name: &str, value: &str) -> Result<T, FieldError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e: T::Err| FieldError::new(name, &format!("invalid value `{value}`: {e}")))
+}
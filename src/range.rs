@@ -0,0 +1,303 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::body::Body;
+use crate::conditional::if_range_matches;
+use crate::request::Request;
+use crate::response::Response;
+use crate::status::StatusCode;
+
+const MAX_RANGES: usize = 16;
+
+pub(crate) fn apply_range(request: &Request, response: &mut Response) {
+    if response.status_code != 200 || !matches!(request.method.as_str(), "GET" | "HEAD") {
+        return;
+    }
+    let Some(header) = request.get_header("Range") else {
+        return;
+    };
+    if !if_range_matches(request, response) {
+        return;
+    }
+    let total = match &response.body {
+        Body::File { offset: 0, length, .. } => *length,
+        _ => return,
+    };
+    let Some(ranges) = parse_ranges(header, total) else {
+        return;
+    };
+
+    if ranges.is_empty() {
+        let mut unsatisfiable = Response::with_status(StatusCode::RANGE_NOT_SATISFIABLE, "");
+        unsatisfiable.headers.remove("Content-Type");
+        unsatisfiable.set_header("Accept-Ranges", "bytes");
+        unsatisfiable.set_header("Content-Range", &format!("bytes */{total}"));
+        *response = unsatisfiable;
+        return;
+    }
+
+    let Body::File { file, .. } = std::mem::replace(&mut response.body, Body::empty()) else {
+        unreachable!("body was checked to be a file above");
+    };
+
+    response.set_status(StatusCode::PARTIAL_CONTENT);
+
+    if let [(start, end)] = ranges[..] {
+        response.set_header("Content-Range", &format!("bytes {start}-{end}/{total}"));
+        response.set_body(Body::File {
+            file,
+            offset: start,
+            length: end - start + 1,
+        });
+        return;
+    }
+
+    let content_type = response
+        .headers
+        .get("Content-Type")
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let boundary = boundary();
+
+    let mut parts = VecDeque::new();
+    let mut length = 0;
+    for (index, (start, end)) in ranges.iter().enumerate() {
+        let separator = if index == 0 { "" } else { "\r\n" };
+        let head = format!(
+            "{separator}--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: bytes {start}-{end}/{total}\r\n\r\n"
+        );
+        length += head.len() as u64 + (end - start + 1);
+        parts.push_back(Part::Bytes(head.into_bytes()));
+        parts.push_back(Part::File {
+            offset: *start,
+            remaining: end - start + 1,
+        });
+    }
+    let tail = format!("\r\n--{boundary}--\r\n");
+    length += tail.len() as u64;
+    parts.push_back(Part::Bytes(tail.into_bytes()));
+
+    response.set_header("Content-Type", &format!("multipart/byteranges; boundary={boundary}"));
+    response.set_body(Body::reader(MultiRangeReader { file, parts }, Some(length)));
+}
+
+fn parse_ranges(header: &str, total: u64) -> Option<Vec<(u64, u64)>> {
+    let specs = header.trim().strip_prefix("bytes=")?;
+    let mut ranges = Vec::new();
+
+    for spec in specs.split(',') {
+        let (start, end) = spec.trim().split_once('-')?;
+        let range = match (start.trim(), end.trim()) {
+            ("", "") => return None,
+            ("", suffix) => {
+                let suffix: u64 = suffix.parse().ok()?;
+                (suffix > 0 && total > 0).then(|| (total.saturating_sub(suffix), total - 1))
+            }
+            (start, "") => {
+                let start: u64 = start.parse().ok()?;
+                (start < total).then(|| (start, total - 1))
+            }
+            (start, end) => {
+                let start: u64 = start.parse().ok()?;
+                let end: u64 = end.parse().ok()?;
+                if end < start {
+                    return None;
+                }
+                (start < total).then(|| (start, end.min(total - 1)))
+            }
+        };
+        ranges.extend(range);
+    }
+
+    if ranges.len() > MAX_RANGES {
+        return None;
+    }
+
+    ranges.sort_unstable();
+    let mut coalesced: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match coalesced.last_mut() {
+            Some((_, last_end)) if start <= last_end.saturating_add(1) => *last_end = (*last_end).max(end),
+            _ => coalesced.push((start, end)),
+        }
+    }
+
+    Some(coalesced)
+}
+
+fn boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    format!("syne-{:016x}", nanos as u64 ^ 0x9e37_79b9_7f4a_7c15)
+}
+
+enum Part {
+    Bytes(Vec<u8>),
+    File { offset: u64, remaining: u64 },
+}
+
+struct MultiRangeReader {
+    file: File,
+    parts: VecDeque<Part>,
+}
+
+impl Read for MultiRangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let Some(part) = self.parts.front_mut() else {
+                return Ok(0);
+            };
+
+            match part {
+                Part::Bytes(bytes) if bytes.is_empty() => {
+                    self.parts.pop_front();
+                }
+                Part::Bytes(bytes) => {
+                    let count = bytes.len().min(buf.len());
+                    buf[..count].copy_from_slice(&bytes[..count]);
+                    bytes.drain(..count);
+                    return Ok(count);
+                }
+                Part::File { remaining: 0, .. } => {
+                    self.parts.pop_front();
+                }
+                Part::File { offset, remaining } => {
+                    let limit = (*remaining).min(buf.len() as u64) as usize;
+                    self.file.seek(SeekFrom::Start(*offset))?;
+                    let count = self.file.read(&mut buf[..limit])?;
+                    if count == 0 {
+                        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                    }
+                    *offset += count as u64;
+                    *remaining -= count as u64;
+                    return Ok(count);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::crypto::{base64_url_encode, random_bytes};
+    use crate::header::HeaderMap;
+
+    const CONTENT: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    fn ranged(range: &str, if_range: Option<&str>) -> Response {
+        let path = std::env::temp_dir().join(format!("syne-range-{}", base64_url_encode(&random_bytes(8))));
+        fs::write(&path, CONTENT).unwrap();
+        let mut response = Response::file(&path);
+        fs::remove_file(&path).unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Range", range).unwrap();
+        if let Some(if_range) = if_range {
+            headers.insert("If-Range", if_range).unwrap();
+        }
+        apply_range(&Request::new("GET", "/file.txt", headers, Vec::new()), &mut response);
+        response
+    }
+
+    fn body(response: &mut Response) -> Vec<u8> {
+        let length = response.body.len();
+        let mut body = Vec::new();
+        response.body.write_to(&mut body).unwrap();
+        assert_eq!(length, Some(body.len() as u64));
+        body
+    }
+
+    #[test]
+    fn parses_single_ranges() {
+        assert_eq!(parse_ranges("bytes=0-9", 100), Some(vec![(0, 9)]));
+        assert_eq!(parse_ranges(" bytes= 10 - 19 ", 100), Some(vec![(10, 19)]));
+        assert_eq!(parse_ranges("bytes=90-", 100), Some(vec![(90, 99)]));
+        assert_eq!(parse_ranges("bytes=90-500", 100), Some(vec![(90, 99)]));
+        assert_eq!(parse_ranges("bytes=99-99", 100), Some(vec![(99, 99)]));
+    }
+
+    #[test]
+    fn parses_suffix_ranges() {
+        assert_eq!(parse_ranges("bytes=-10", 100), Some(vec![(90, 99)]));
+        assert_eq!(parse_ranges("bytes=-500", 100), Some(vec![(0, 99)]));
+        assert_eq!(parse_ranges("bytes=-0", 100), Some(Vec::new()));
+        assert_eq!(parse_ranges("bytes=-10", 0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn coalesces_overlapping_and_adjacent_ranges() {
+        assert_eq!(parse_ranges("bytes=0-9,5-14", 100), Some(vec![(0, 14)]));
+        assert_eq!(parse_ranges("bytes=10-19,0-9", 100), Some(vec![(0, 19)]));
+        assert_eq!(parse_ranges("bytes=0-9,0-9,0-9", 100), Some(vec![(0, 9)]));
+        assert_eq!(parse_ranges("bytes=50-59,0-9,-5", 100), Some(vec![(0, 9), (50, 59), (95, 99)]));
+        assert_eq!(parse_ranges("bytes=0-,10-20", 100), Some(vec![(0, 99)]));
+    }
+
+    #[test]
+    fn drops_unsatisfiable_ranges() {
+        assert_eq!(parse_ranges("bytes=100-", 100), Some(Vec::new()));
+        assert_eq!(parse_ranges("bytes=100-200", 100), Some(Vec::new()));
+        assert_eq!(parse_ranges("bytes=100-200,0-0", 100), Some(vec![(0, 0)]));
+        assert_eq!(parse_ranges("bytes=0-0", 0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn ignores_invalid_headers() {
+        for header in ["items=0-9", "bytes=", "bytes=-", "bytes=9-0", "bytes=a-9", "bytes=0-9,", "bytes=0", "bytes=1-2-3", "bytes=--1"] {
+            assert_eq!(parse_ranges(header, 100), None, "{header}");
+        }
+        let too_many = (0..=MAX_RANGES).map(|i| format!("{}-{}", i * 2, i * 2)).collect::<Vec<_>>().join(",");
+        assert_eq!(parse_ranges(&format!("bytes={too_many}"), 100), None);
+    }
+
+    #[test]
+    fn answers_single_range_with_partial_content() {
+        let mut response = ranged("bytes=-6", None);
+
+        assert_eq!(response.status_code, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers.get("Content-Range"), Some("bytes 30-35/36"));
+        assert_eq!(body(&mut response), b"uvwxyz");
+    }
+
+    #[test]
+    fn answers_multiple_ranges_with_multipart_body() {
+        let mut response = ranged("bytes=0-1,10-11", None);
+        let content_type = response.headers.get("Content-Type").unwrap().to_string();
+        let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap();
+        let body = String::from_utf8(body(&mut response)).unwrap();
+
+        assert_eq!(response.status_code, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            body,
+            format!(
+                "--{boundary}\r\nContent-Type: application/octet-stream\r\nContent-Range: bytes 0-1/36\r\n\r\n01\r\n\
+                 --{boundary}\r\nContent-Type: application/octet-stream\r\nContent-Range: bytes 10-11/36\r\n\r\nab\r\n\
+                 --{boundary}--\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn answers_unsatisfiable_ranges_with_416() {
+        let response = ranged("bytes=36-", None);
+
+        assert_eq!(response.status_code, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers.get("Content-Range"), Some("bytes */36"));
+        assert_eq!(response.headers.get("Content-Type"), None);
+    }
+
+    #[test]
+    fn serves_full_content_for_invalid_or_stale_ranges() {
+        let mut response = ranged("bytes=9-0", None);
+        assert_eq!(response.status_code, StatusCode::OK);
+        assert_eq!(body(&mut response), CONTENT);
+
+        let response = ranged("bytes=0-1", Some("\"stale\""));
+        assert_eq!(response.status_code, StatusCode::OK);
+    }
+}
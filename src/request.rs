@@ -1,99 +1,196 @@
-use std::collections::HashMap;
+use std::any::TypeId;
+use std::sync::{Arc, Mutex};
+use crate::cookie::parse_cookie_header;
+use crate::extract::StateMap;
+use crate::header::HeaderMap;
+use crate::json::{FromJson, Json, JsonError};
+use crate::mime;
+use crate::multipart::{Multipart, MultipartConfig, MultipartError, Upload};
+use crate::params::{FieldError, FormError, FromForm, FromQuery, ParamMap, FORM_URLENCODED};
+use crate::session::Session;
 
 pub struct Request {
     pub method: String,
     pub path: String,
-    pub headers: HashMap<String, String>,
-    pub body: String,
-    pub query_params: HashMap<String, String>,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub query_string: String,
+    pub query_params: ParamMap,
+    pub params: ParamMap,
+    pub(crate) session: Option<Session>,
+    pub(crate) states: Option<Arc<StateMap>>,
+    pub(crate) upload: Mutex<Upload>,
 }
 
 impl Request {
-    pub fn new(method: &str, path: &str, headers: HashMap<String, String>, body: String) -> Self {
-        let (path, query_params) = Self::parse_path_and_query(path);
+    pub fn new(method: &str, path: &str, headers: HeaderMap, body: Vec<u8>) -> Self {
+        let (path, query_string, query_params) = Self::parse_path_and_query(path);
 
         Request {
             method: method.to_string(),
             path,
             headers,
             body,
+            query_string,
             query_params,
+            params: ParamMap::new(),
+            session: None,
+            states: None,
+            upload: Mutex::new(Upload::Buffered),
         }
     }
 
-    pub fn get_header(&self, name: &str) -> Option<&String> {
+    pub fn get_header(&self, name: &str) -> Option<&str> {
         self.headers.get(name)
     }
 
-    pub fn get_query_param(&self, name: &str) -> Option<&String> {
+    pub fn get_headers(&self, name: &str) -> Vec<&str> {
+        self.headers.get_all(name)
+    }
+
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies()
+            .into_iter()
+            .find(|(cookie_name, _)| *cookie_name == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn cookies(&self) -> Vec<(&str, &str)> {
+        self.headers
+            .get_all("Cookie")
+            .into_iter()
+            .flat_map(parse_cookie_header)
+            .collect()
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name)
+    }
+
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.states
+            .as_ref()?
+            .get(&TypeId::of::<T>())
+            .and_then(|state| state.downcast_ref())
+    }
+
+    pub fn text(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+
+    pub fn get_query_param(&self, name: &str) -> Option<&str> {
         self.query_params.get(name)
     }
 
-    fn parse_path_and_query(full_path: &str) -> (String, HashMap<String, String>) {
-        if let Some((path, query_string)) = full_path.split_once('?') {
-            let mut query_params = HashMap::new();
+    pub fn get_all_query(&self, name: &str) -> Vec<&str> {
+        self.query_params.get_all(name)
+    }
 
-            for param in query_string.split('&') {
-                if let Some((key, value)) = param.split_once('=') {
-                    query_params.insert(
-                        Self::url_decode(key),
-                        Self::url_decode(value),
-                    );
-                }
-            }
+    pub fn has_query(&self, name: &str) -> bool {
+        self.query_params.contains(name)
+    }
 
-            (path.to_string(), query_params)
-        } else {
-            (full_path.to_string(), HashMap::new())
-        }
+    pub fn query<T: FromQuery>(&self) -> Result<T, FieldError> {
+        T::from_query(&self.query_params)
+    }
+
+    pub fn form<T: FromForm>(&self) -> Result<T, FormError> {
+        let body = self.form_body()?;
+        Ok(T::from_form(&ParamMap::parse(body))?)
+    }
+
+    pub fn json<T: FromJson>(&self) -> Result<T, JsonError> {
+        self.require_json()?;
+        let body = self.text().map_err(|_| JsonError::Syntax {
+            message: "invalid UTF-8".to_string(),
+            line: 1,
+            column: 1,
+        })?;
+        T::from_json(&Json::parse(body)?)
     }
 
-    fn url_decode(input: &str) -> String {
-        let mut result = String::new();
-        let mut chars = input.chars();
-
-        while let Some(ch) = chars.next() {
-            match ch {
-                '%' => {
-                    if let (Some(high), Some(low)) = (chars.next(), chars.next()) {
-                        if let (Some(high_digit), Some(low_digit)) =
-                            (Self::hex_to_u8(high), Self::hex_to_u8(low)) {
-                            let byte = (high_digit << 4) | low_digit;
-                            if byte < 128 {
-                                result.push(byte as char);
-                            } else {
-                                let bytes = vec![byte];
-                                if let Ok(s) = String::from_utf8(bytes) {
-                                    result.push_str(&s);
-                                } else {
-                                    result.push('%');
-                                    result.push(high);
-                                    result.push(low);
-                                }
-                            }
-                        } else {
-                            result.push('%');
-                            result.push(high);
-                            result.push(low);
-                        }
-                    } else {
-                        result.push('%');
-                    }
-                }
-                '+' => result.push(' '),
-                _ => result.push(ch),
+    #[cfg(feature = "serde")]
+    pub fn deserialize_query<T: serde::de::DeserializeOwned>(&self) -> Result<T, FieldError> {
+        serde_urlencoded::from_str(&self.query_string).map_err(|e| FieldError::new("", &e.to_string()))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn deserialize_form<T: serde::de::DeserializeOwned>(&self) -> Result<T, FormError> {
+        let body = self.form_body()?;
+        serde_urlencoded::from_str(body).map_err(|e| FormError::Field(FieldError::new("", &e.to_string())))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn deserialize_json<T: serde::de::DeserializeOwned>(&self) -> Result<T, JsonError> {
+        self.require_json()?;
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    pub fn multipart(&self) -> Result<Multipart, MultipartError> {
+        self.read_multipart(None)
+    }
+
+    pub fn multipart_with(&self, config: &MultipartConfig) -> Result<Multipart, MultipartError> {
+        self.read_multipart(Some(config))
+    }
+
+    fn read_multipart(&self, config: Option<&MultipartConfig>) -> Result<Multipart, MultipartError> {
+        let mut upload = self.upload.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Upload::Streamed(streamed, _) | Upload::Read(streamed) = &*upload
+            && config.is_some_and(|config| config != streamed)
+        {
+            return Err(MultipartError::ConfigMismatch);
+        }
+
+        match std::mem::replace(&mut *upload, Upload::Buffered) {
+            Upload::Buffered => {
+                let default = MultipartConfig::default();
+                config
+                    .unwrap_or(&default)
+                    .read(self.get_header("Content-Type"), self.body.as_slice())
+            }
+            Upload::Streamed(config, result) => {
+                *upload = Upload::Read(config);
+                result
+            }
+            Upload::Read(config) => {
+                *upload = Upload::Read(config);
+                Err(MultipartError::AlreadyRead)
             }
         }
+    }
+
+    fn form_body(&self) -> Result<&str, FormError> {
+        let is_form = self
+            .get_header("Content-Type")
+            .is_some_and(|content_type| mime::essence(content_type) == FORM_URLENCODED);
+        if !is_form {
+            return Err(FormError::UnsupportedMediaType);
+        }
 
-        result
+        self.text().map_err(|_| FormError::InvalidBody)
     }
 
-    fn hex_to_u8(ch: char) -> Option<u8> {
-        match ch {
-            '0'..='9' => Some((ch as u8) - b'0'),
-            'A'..='F' => Some((ch as u8) - b'A' + 10),
-            'a'..='f' => Some((ch as u8) - b'a' + 10),
-            _ => None,
+    fn require_json(&self) -> Result<(), JsonError> {
+        let is_json = self.get_header("Content-Type").is_some_and(|content_type| {
+            let essence = mime::essence(content_type);
+            essence == "application/json" || essence.ends_with("+json")
+        });
+        if is_json {
+            Ok(())
+        } else {
+            Err(JsonError::UnsupportedMediaType)
         }
     }
-}
\ No newline at end of file
+
+    fn parse_path_and_query(full_path: &str) -> (String, String, ParamMap) {
+        match full_path.split_once('?') {
+            Some((path, query_string)) => (path.to_string(), query_string.to_string(), ParamMap::parse(query_string)),
+            None => (full_path.to_string(), String::new(), ParamMap::new()),
+        }
+    }
+}
@@ -1,51 +1,257 @@
-use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::body::Body;
+use crate::cookie::Cookie;
+use crate::date::format_http_date;
+use crate::header::{HeaderMap, InvalidHeader};
+use crate::json::Json;
+use crate::mime;
+use crate::status::StatusCode;
+use crate::warn;
+#[cfg(feature = "serde")]
+use crate::error;
 
 pub struct Response {
-    pub status_code: u16,
+    pub status_code: StatusCode,
     pub status_text: String,
-    pub headers: HashMap<String, String>,
-    pub body: String,
+    pub headers: HeaderMap,
+    pub body: Body,
 }
 
 impl Response {
-    pub fn new(status_code: u16, status_text: &str, body: &str) -> Self {
-        let mut headers = HashMap::new();
-        headers.insert("Content-Type".to_string(), "text/html; charset=utf-8".to_string());
-        headers.insert("Content-Length".to_string(), body.len().to_string());
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::new()
+    }
 
-        Response {
-            status_code,
+    pub fn new(status_code: impl Into<StatusCode>, status_text: &str, body: impl AsRef<[u8]>) -> Self {
+        let mut response = Response {
+            status_code: status_code.into(),
             status_text: status_text.to_string(),
-            headers,
-            body: body.to_string(),
-        }
+            headers: HeaderMap::new(),
+            body: Body::Bytes(body.as_ref().to_vec()),
+        };
+        response.set_header("Content-Type", "text/html; charset=utf-8");
+        response
+    }
+
+    pub fn with_status(status_code: impl Into<StatusCode>, body: impl AsRef<[u8]>) -> Self {
+        let status_code = status_code.into();
+        Self::new(status_code, status_code.canonical_reason(), body)
+    }
+
+    pub fn redirect(location: &str) -> Self {
+        Self::redirect_with(StatusCode::FOUND, location)
+    }
+
+    pub fn redirect_permanent(location: &str) -> Self {
+        Self::redirect_with(StatusCode::MOVED_PERMANENTLY, location)
+    }
+
+    pub fn see_other(location: &str) -> Self {
+        Self::redirect_with(StatusCode::SEE_OTHER, location)
+    }
+
+    pub fn temporary_redirect(location: &str) -> Self {
+        Self::redirect_with(StatusCode::TEMPORARY_REDIRECT, location)
+    }
+
+    pub fn permanent_redirect(location: &str) -> Self {
+        Self::redirect_with(StatusCode::PERMANENT_REDIRECT, location)
     }
 
-    pub fn json(status_code: u16, json_body: &str) -> Self {
-        let mut response = Self::new(status_code, "OK", json_body);
+    pub(crate) fn redirect_with(status_code: StatusCode, location: &str) -> Self {
+        let mut response = Self::with_status(status_code, "");
+        response.headers.remove("Content-Type");
+        response.set_header("Location", location);
+        response
+    }
+
+    pub fn json(status_code: impl Into<StatusCode>, json_body: &str) -> Self {
+        let mut response = Self::with_status(status_code, json_body);
         response.set_header("Content-Type", "application/json");
         response
     }
 
-    pub fn html(status_code: u16, html_body: &str) -> Self {
-        let mut response = Self::new(status_code, "OK", html_body);
+    pub fn json_value(status_code: impl Into<StatusCode>, value: &Json) -> Self {
+        Self::json(status_code, &value.to_string())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn json_from<T: serde::Serialize>(status_code: impl Into<StatusCode>, value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self::json(status_code, &body),
+            Err(e) => {
+                error!("Failed to serialize response: {e}");
+                Self::with_status(StatusCode::INTERNAL_SERVER_ERROR, "Failed to serialize response")
+            }
+        }
+    }
+
+    pub fn html(status_code: impl Into<StatusCode>, html_body: &str) -> Self {
+        let mut response = Self::with_status(status_code, html_body);
         response.set_header("Content-Type", "text/html; charset=utf-8");
         response
     }
 
-    pub fn text(status_code: u16, text_body: &str) -> Self {
-        let mut response = Self::new(status_code, "OK", text_body);
+    pub fn text(status_code: impl Into<StatusCode>, text_body: &str) -> Self {
+        let mut response = Self::with_status(status_code, text_body);
         response.set_header("Content-Type", "text/plain; charset=utf-8");
         response
     }
 
+    pub fn bytes(status_code: impl Into<StatusCode>, content_type: &str, body: Vec<u8>) -> Self {
+        let mut response = Self::with_status(status_code, body);
+        response.set_header("Content-Type", content_type);
+        response
+    }
+
+    pub fn stream<R: Read + Send + 'static>(status_code: impl Into<StatusCode>, content_type: &str, reader: R) -> Self {
+        let mut response = Self::with_status(status_code, "");
+        response.set_header("Content-Type", content_type);
+        response.set_body(Body::reader(reader, None));
+        response
+    }
+
+    pub fn file(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+
+        if !path.is_file() {
+            return Response::with_status(StatusCode::NOT_FOUND, "File not found");
+        }
+
+        match File::open(path).and_then(|file| Ok((file.metadata()?, Body::file(file)?))) {
+            Ok((metadata, body)) => {
+                let mut response = Self::with_status(StatusCode::OK, "");
+                response.set_header("Accept-Ranges", "bytes");
+                if let Ok(modified) = metadata.modified() {
+                    let nanos = modified
+                        .duration_since(UNIX_EPOCH)
+                        .map(|duration| duration.as_nanos())
+                        .unwrap_or_default();
+                    response.set_etag(&format!("{:x}-{:x}", metadata.len(), nanos));
+                    response.set_last_modified(modified);
+                }
+                response.set_header("Content-Type", &mime::from_path(path));
+                response.set_body(body);
+                response
+            }
+            Err(_) => Response::with_status(StatusCode::INTERNAL_SERVER_ERROR, "Could not read the file"),
+        }
+    }
+
+    pub fn chunks<I>(status_code: impl Into<StatusCode>, content_type: &str, chunks: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Vec<u8>>,
+        I::IntoIter: Send + 'static,
+    {
+        let mut response = Self::with_status(status_code, "");
+        response.set_header("Content-Type", content_type);
+        response.set_body(Body::chunks(chunks));
+        response
+    }
+
+    pub fn set_body(&mut self, body: impl Into<Body>) {
+        self.body = body.into();
+    }
+
+    pub fn set_status(&mut self, status_code: impl Into<StatusCode>) {
+        self.status_code = status_code.into();
+        self.status_text = self.status_code.canonical_reason().to_string();
+    }
+
+    pub fn set_status_text(&mut self, status_text: &str) {
+        self.status_text = status_text.to_string();
+    }
+
+    pub fn set_etag(&mut self, etag: &str) {
+        if etag.starts_with('"') || etag.starts_with("W/\"") {
+            self.set_header("ETag", etag);
+        } else {
+            self.set_header("ETag", &format!("\"{etag}\""));
+        }
+    }
+
+    pub fn set_last_modified(&mut self, time: SystemTime) {
+        self.set_header("Last-Modified", &format_http_date(time));
+    }
+
     pub fn set_header(&mut self, name: &str, value: &str) {
-        self.headers.insert(name.to_string(), value.to_string());
-        if name.to_lowercase() != "content-length" {
-            self.headers.insert("Content-Length".to_string(), self.body.len().to_string());
+        if let Err(e) = self.headers.insert(name, value) {
+            warn!("Dropping response header: {e}");
         }
     }
 
+    pub(crate) fn sync_length_headers(&mut self) {
+        if !self.allows_body() {
+            self.headers.remove("Content-Length");
+            self.headers.remove("Transfer-Encoding");
+            return;
+        }
+
+        match self.body.len() {
+            Some(length) => {
+                self.headers.remove("Transfer-Encoding");
+                self.headers
+                    .insert("Content-Length", &length.to_string())
+                    .expect("Content-Length is a valid header");
+            }
+            None => {
+                self.headers.remove("Content-Length");
+                self.headers
+                    .insert("Transfer-Encoding", "chunked")
+                    .expect("Transfer-Encoding is a valid header");
+            }
+        }
+    }
+
+    pub fn append_header(&mut self, name: &str, value: &str) {
+        if let Err(e) = self.headers.append(name, value) {
+            warn!("Dropping response header: {e}");
+        }
+    }
+
+    pub fn add_cookie(&mut self, cookie: Cookie) {
+        match cookie.validate() {
+            Ok(()) => self.append_header("Set-Cookie", &cookie.to_string()),
+            Err(e) => {
+                warn!("Dropping cookie: {e}");
+            }
+        }
+    }
+
+    pub fn remove_cookie(&mut self, name: &str) {
+        self.add_cookie(Cookie::new(name, "").path("/").removal());
+    }
+
+    pub fn write_to(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        self.write_head(writer)?;
+        if self.allows_body() {
+            self.body.write_to(writer)?;
+        }
+        writer.flush()
+    }
+
+    pub(crate) fn write_head(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        self.sync_length_headers();
+        if !self.headers.contains("Date") {
+            self.set_header("Date", &format_http_date(SystemTime::now()));
+        }
+
+        let status_text = self.status_text.replace(['\r', '\n'], " ");
+        write!(writer, "HTTP/1.1 {} {}\r\n", self.status_code, status_text)?;
+        for (name, value) in self.headers.iter() {
+            write!(writer, "{name}: {value}\r\n")?;
+        }
+        writer.write_all(b"\r\n")
+    }
+
+    pub(crate) fn allows_body(&self) -> bool {
+        !(self.status_code.is_informational() || self.status_code == 204 || self.status_code == 304)
+    }
+
     pub fn headers_string(&self) -> String {
         self.headers
             .iter()
@@ -53,4 +259,83 @@ impl Response {
             .collect::<Vec<_>>()
             .join("\r\n")
     }
-}
\ No newline at end of file
+}
+
+pub struct ResponseBuilder {
+    status_code: StatusCode,
+    status_text: Option<String>,
+    headers: HeaderMap,
+    error: Option<InvalidHeader>,
+}
+
+impl Default for ResponseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResponseBuilder {
+    pub fn new() -> Self {
+        ResponseBuilder {
+            status_code: StatusCode::OK,
+            status_text: None,
+            headers: HeaderMap::new(),
+            error: None,
+        }
+    }
+
+    pub fn status(mut self, status_code: impl Into<StatusCode>) -> Self {
+        self.status_code = status_code.into();
+        self
+    }
+
+    pub fn status_text(mut self, status_text: &str) -> Self {
+        self.status_text = Some(status_text.to_string());
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        if let Err(e) = self.headers.append(name, value) {
+            self.error.get_or_insert(e);
+        }
+        self
+    }
+
+    pub fn cookie(mut self, cookie: Cookie) -> Self {
+        match cookie.validate() {
+            Ok(()) => self.header("Set-Cookie", &cookie.to_string()),
+            Err(e) => {
+                self.error.get_or_insert(e);
+                self
+            }
+        }
+    }
+
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        if let Err(e) = self.headers.insert("Content-Type", content_type) {
+            self.error.get_or_insert(e);
+        }
+        self
+    }
+
+    pub fn body(self, body: impl Into<Body>) -> Result<Response, InvalidHeader> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        let status_text = match self.status_text {
+            Some(status_text) if status_text.contains(['\r', '\n']) => {
+                return Err(InvalidHeader::Value(":status".to_string()));
+            }
+            Some(status_text) => status_text,
+            None => self.status_code.canonical_reason().to_string(),
+        };
+
+        Ok(Response {
+            status_code: self.status_code,
+            status_text,
+            headers: self.headers,
+            body: body.into(),
+        })
+    }
+}
@@ -1,6 +1,19 @@
-use std::collections::HashMap;
+use std::any::TypeId;
+use std::sync::Arc;
+use crate::compress::Compression;
+use crate::conditional::apply_conditional;
+use crate::extract::{Handler as ExtractHandler, StateMap};
+use crate::header::HeaderMap;
+use crate::mime::MimeTypes;
+use crate::multipart::{is_multipart, MultipartConfig};
+use crate::params::ParamMap;
+use crate::range::apply_range;
 use crate::request::Request;
 use crate::response::Response;
+use crate::session::Sessions;
+use crate::status::StatusCode;
+use crate::static_files::{strip_mount, StaticFiles};
+use crate::url::{decode_path, decode_unreserved, encode_path_segment};
 
 pub type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync>;
 
@@ -10,9 +23,29 @@ pub struct Route {
     pub handler: Handler,
 }
 
+const DEFAULT_MAX_BODY_SIZE: u64 = 10 * 1024 * 1024;
+
+const SERVER: &str = concat!("syne/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+    #[default]
+    Strict,
+    Redirect,
+    MatchBoth,
+}
+
 pub struct Router {
     routes: Vec<Route>,
-    static_routes: HashMap<String, String>,
+    static_routes: Vec<(String, StaticFiles)>,
+    trailing_slash: TrailingSlash,
+    mime_types: MimeTypes,
+    compression: Option<Compression>,
+    server_header: bool,
+    max_body_size: u64,
+    multipart: Option<MultipartConfig>,
+    sessions: Option<Sessions>,
+    states: Arc<StateMap>,
 }
 
 impl Default for Router {
@@ -25,10 +58,22 @@ impl Router {
     pub fn new() -> Self {
         Router {
             routes: Vec::new(),
-            static_routes: HashMap::new(),
+            static_routes: Vec::new(),
+            trailing_slash: TrailingSlash::default(),
+            mime_types: MimeTypes::new(),
+            compression: None,
+            server_header: false,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            multipart: None,
+            sessions: None,
+            states: Arc::new(StateMap::new()),
         }
     }
 
+    pub fn trailing_slash(&mut self, policy: TrailingSlash) {
+        self.trailing_slash = policy;
+    }
+
     pub fn get<F>(&mut self, path: &str, handler: F)
     where
         F: Fn(&Request) -> Response + Send + Sync + 'static,
@@ -73,64 +118,239 @@ impl Router {
         });
     }
 
+    pub fn route<H, Args>(&mut self, method: &str, path: &str, handler: H)
+    where
+        H: ExtractHandler<Args>,
+    {
+        self.routes.push(Route {
+            method: method.to_ascii_uppercase(),
+            path: path.to_string(),
+            handler: Box::new(move |request| handler.call(request)),
+        });
+    }
+
+    pub fn state<T: Send + Sync + 'static>(&mut self, state: T) {
+        let states = Arc::get_mut(&mut self.states).expect("State must be added before the router is shared");
+        states.insert(TypeId::of::<T>(), Box::new(state));
+    }
+
+    pub fn compression(&mut self, compression: Compression) {
+        self.compression = Some(compression);
+    }
+
+    pub fn server_header(&mut self, enabled: bool) {
+        self.server_header = enabled;
+    }
+
+    pub fn max_body_size(&mut self, bytes: u64) {
+        self.max_body_size = bytes;
+    }
+
+    pub(crate) fn body_limit(&self) -> u64 {
+        self.max_body_size
+    }
+
+    pub fn multipart(&mut self, config: MultipartConfig) {
+        self.multipart = Some(config);
+    }
+
+    pub(crate) fn streamed_multipart(&self, headers: &HeaderMap) -> Option<&MultipartConfig> {
+        let content_type = headers.get("Content-Type")?;
+        self.multipart.as_ref().filter(|_| is_multipart(content_type))
+    }
+
+    pub fn sessions(&mut self, sessions: Sessions) {
+        self.sessions = Some(sessions);
+    }
+
+    pub fn mime_type(&mut self, extension: &str, content_type: &str) {
+        self.mime_types.insert(extension, content_type);
+    }
+
     pub fn static_files(&mut self, route_path: &str, file_path: &str) {
-        self.static_routes.insert(route_path.to_string(), file_path.to_string());
+        self.mount_static(route_path, StaticFiles::new(file_path));
+    }
+
+    pub fn mount_static(&mut self, route_path: &str, static_files: StaticFiles) {
+        self.static_routes.retain(|(existing, _)| existing != route_path);
+        self.static_routes.push((route_path.to_string(), static_files));
+        self.static_routes.sort_by_key(|(route_path, _)| std::cmp::Reverse(route_path.trim_end_matches('/').len()));
+    }
+
+    pub fn redirect(&mut self, from: &str, to: &str, status_code: u16) {
+        assert!(
+            matches!(status_code, 301 | 302 | 303 | 307 | 308),
+            "Redirect status code must be 301, 302, 303, 307 or 308, got {status_code}"
+        );
+        let status_code = StatusCode::from(status_code);
+        let to = to.to_string();
+        self.routes.push(Route {
+            method: "*".to_string(),
+            path: from.to_string(),
+            handler: Box::new(move |request| {
+                let mut location = substitute_params(&to, request);
+                if !location.contains('?') && !request.query_string.is_empty() {
+                    location = format!("{location}?{}", request.query_string);
+                }
+                Response::redirect_with(status_code, &location)
+            }),
+        });
+    }
+
+    pub fn handle_request(&self, request: &mut Request) -> Response {
+        request.states = Some(Arc::clone(&self.states));
+        if let Some(sessions) = &self.sessions {
+            sessions.load(request);
+        }
+        let mut response = self.route_request(request);
+        if let Some(sessions) = &self.sessions {
+            sessions.commit(request, &mut response);
+        }
+        apply_conditional(request, &mut response);
+        apply_range(request, &mut response);
+        if let Some(compression) = &self.compression {
+            compression.apply(request, &mut response);
+        }
+        if self.server_header && !response.headers.contains("Server") {
+            response.set_header("Server", SERVER);
+        }
+        response
     }
 
-    pub fn handle_request(&self, request: &Request) -> Response {
+    fn route_request(&self, request: &mut Request) -> Response {
+        let mut path = normalize_path(&request.path);
+
+        if self.trailing_slash == TrailingSlash::Redirect {
+            if !self.has_route(&request.method, &path) {
+                let alternate = toggle_trailing_slash(&path);
+                if self.has_route(&request.method, &alternate) {
+                    path = alternate;
+                }
+            }
+            if path != request.path {
+                return Self::redirect_to(&path, &request.query_string);
+            }
+        }
+
         for route in &self.routes {
-            if route.method == request.method && self.path_matches(&route.path, &request.path) {
+            if !method_matches(&route.method, &request.method) {
+                continue;
+            }
+            if let Some(params) = self.match_path(&route.path, &path) {
+                request.params = params;
                 return (route.handler)(request);
             }
         }
 
-        for (route_path, file_path) in &self.static_routes {
-            if request.path.starts_with(route_path) {
-                return self.serve_static_file(&request.path, file_path);
+        for (route_path, static_files) in &self.static_routes {
+            if let Some(relative_path) = strip_mount(&path, route_path) {
+                return static_files.serve(request, relative_path, &self.mime_types);
             }
         }
 
-        Response::new(404, "Not Found", "Page not found")
+        Response::with_status(StatusCode::NOT_FOUND, "Page not found")
     }
 
-    fn path_matches(&self, route_path: &str, request_path: &str) -> bool {
-        route_path == request_path
+    fn has_route(&self, method: &str, path: &str) -> bool {
+        self.routes
+            .iter()
+            .any(|route| method_matches(&route.method, method) && self.match_path(&route.path, path).is_some())
     }
 
-    fn serve_static_file(&self, request_path: &str, base_path: &str) -> Response {
-        use std::fs;
-        use std::path::Path;
-
-        let file_path = format!("{}/{}", base_path, request_path.trim_start_matches('/'));
-        let path = Path::new(&file_path);
-
-        if path.exists() && path.is_file() {
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    let mut response = Response::new(200, "OK", &content);
+    fn match_path(&self, route_path: &str, request_path: &str) -> Option<ParamMap> {
+        let (route_path, request_path) = match self.trailing_slash {
+            TrailingSlash::MatchBoth => (trim_trailing_slash(route_path), trim_trailing_slash(request_path)),
+            TrailingSlash::Strict | TrailingSlash::Redirect => (route_path, request_path),
+        };
 
-                    if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                        let content_type = match ext {
-                            "html" => "text/html",
-                            "css" => "text/css",
-                            "js" => "application/javascript",
-                            "json" => "application/json",
-                            "png" => "image/png",
-                            "jpg" | "jpeg" => "image/jpeg",
-                            "gif" => "image/gif",
-                            _ => "text/plain",
-                        };
-                        response.set_header("Content-Type", content_type);
-                    }
+        let route_segments: Vec<&str> = route_path.split('/').collect();
+        let request_segments: Vec<&str> = request_path.split('/').collect();
+        if route_segments.len() != request_segments.len() {
+            return None;
+        }
 
-                    response
+        let mut params = ParamMap::new();
+        for (route_segment, request_segment) in route_segments.iter().zip(&request_segments) {
+            match route_segment.strip_prefix(':') {
+                Some(name) if !request_segment.is_empty() => {
+                    let value = decode_path(request_segment).unwrap_or_else(|_| request_segment.to_string());
+                    params.insert(name, &value);
                 }
-                Err(_) => Response::new(500, "Internal Server Error", "Could not read the file"),
+                Some(_) => return None,
+                None if route_segment != request_segment => return None,
+                None => {}
             }
+        }
+
+        Some(params)
+    }
+
+    fn redirect_to(path: &str, query_string: &str) -> Response {
+        let location = if query_string.is_empty() {
+            path.to_string()
         } else {
-            Response::new(404, "Not Found", "File not found")
+            format!("{path}?{query_string}")
+        };
+
+        Response::permanent_redirect(&location)
+    }
+}
+
+pub(crate) fn normalize_path(path: &str) -> String {
+    let mut segments: Vec<String> = Vec::new();
+
+    for segment in path.split('/') {
+        let segment = decode_unreserved(segment);
+        match segment.as_str() {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
         }
     }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    let last = path.rsplit('/').next().map(decode_unreserved);
+    let ends_in_directory = matches!(last.as_deref(), Some("" | "." | ".."));
+    if ends_in_directory && !segments.is_empty() {
+        normalized.push('/');
+    }
+
+    normalized
+}
+
+fn method_matches(route_method: &str, request_method: &str) -> bool {
+    route_method == "*" || route_method == request_method || (route_method == "GET" && request_method == "HEAD")
+}
+
+fn substitute_params(target: &str, request: &Request) -> String {
+    target
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':').and_then(|name| request.param(name)) {
+            Some(value) => encode_path_segment(value),
+            None => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn trim_trailing_slash(path: &str) -> &str {
+    if path.len() > 1 {
+        path.trim_end_matches('/')
+    } else {
+        path
+    }
+}
+
+fn toggle_trailing_slash(path: &str) -> String {
+    if path == "/" {
+        path.to_string()
+    } else if let Some(trimmed) = path.strip_suffix('/') {
+        trimmed.to_string()
+    } else {
+        format!("{path}/")
+    }
 }
 
 #[macro_export]
@@ -147,4 +367,34 @@ macro_rules! route {
     ($router:expr, DELETE $path:literal => $handler:expr) => {
         $router.delete($path, $handler);
     };
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirect_routes_keep_the_query_string() {
+        let mut router = Router::new();
+        router.redirect("/old", "/new", 308);
+
+        let response = router.handle_request(&mut Request::new("GET", "/old?page=2", HeaderMap::new(), Vec::new()));
+        assert_eq!(response.status_code, StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers.get("Location"), Some("/new?page=2"));
+        assert_eq!(response.headers.get("Content-Type"), None);
+    }
+
+    #[test]
+    fn redirect_accepts_redirect_statuses() {
+        for status_code in [301, 302, 303, 307, 308] {
+            Router::new().redirect("/a", "/b", status_code);
+        }
+    }
+
+    #[test]
+    fn redirect_rejects_other_statuses() {
+        for status_code in [200, 300, 304, 305, 306, 404, 42] {
+            let result = std::panic::catch_unwind(|| Router::new().redirect("/a", "/b", status_code));
+            assert!(result.is_err(), "{status_code} was accepted");
+        }
+    }
+}
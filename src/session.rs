@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::cookie::{Cookie, SameSite};
+use crate::crypto::{base64_url_encode, random_bytes};
+use crate::error;
+use crate::request::Request;
+use crate::response::Response;
+use crate::url::{decode_path, encode_path_segment};
+
+const SESSION_ID_BYTES: usize = 32;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+pub type SessionData = HashMap<String, String>;
+
+pub trait SessionStore: Send + Sync {
+    fn load(&self, id: &str) -> Option<(SessionData, SystemTime)>;
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration);
+    fn destroy(&self, id: &str);
+}
+
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, (SessionData, Instant)>>,
+    last_sweep: Mutex<Option<Instant>>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore {
+            sessions: Mutex::new(HashMap::new()),
+            last_sweep: Mutex::new(None),
+        }
+    }
+
+    fn sessions(&self) -> MutexGuard<'_, HashMap<String, (SessionData, Instant)>> {
+        self.sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> Option<(SessionData, SystemTime)> {
+        let mut sessions = self.sessions();
+        let now = Instant::now();
+        match sessions.get(id) {
+            Some((data, expires)) if *expires > now => Some((data.clone(), SystemTime::now() + (*expires - now))),
+            Some(_) => {
+                sessions.remove(id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) {
+        let now = Instant::now();
+        let mut sessions = self.sessions();
+        if sweep_due(&self.last_sweep) {
+            sessions.retain(|_, (_, expires)| *expires > now);
+        }
+        sessions.insert(id.to_string(), (data.clone(), now + ttl));
+    }
+
+    fn destroy(&self, id: &str) {
+        self.sessions().remove(id);
+    }
+}
+
+pub struct FileStore {
+    directory: PathBuf,
+    last_sweep: Mutex<Option<Instant>>,
+}
+
+impl FileStore {
+    pub fn new(directory: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(FileStore {
+            directory,
+            last_sweep: Mutex::new(None),
+        })
+    }
+
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        let valid = !id.is_empty() && id.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_');
+        valid.then(|| self.directory.join(format!("{id}.session")))
+    }
+
+    fn sweep(&self) {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) => {
+                error!("Failed to sweep sessions: {e}");
+                return;
+            }
+        };
+
+        let now = unix_time();
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            if path.extension().is_none_or(|extension| extension != "session") {
+                continue;
+            }
+            let expired = fs::File::open(&path)
+                .and_then(|file| {
+                    let mut line = String::new();
+                    BufReader::new(file).read_line(&mut line)?;
+                    Ok(line)
+                })
+                .is_ok_and(|line| line.trim().parse::<u64>().is_ok_and(|expires| expires <= now));
+            if expired {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+impl SessionStore for FileStore {
+    fn load(&self, id: &str) -> Option<(SessionData, SystemTime)> {
+        let path = self.path(id)?;
+        let content = fs::read_to_string(&path).ok()?;
+        let mut lines = content.lines();
+
+        let expires: u64 = lines.next()?.parse().ok()?;
+        if expires <= unix_time() {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        let data = lines
+            .map(|line| {
+                let (key, value) = line.split_once('=')?;
+                Some((decode_path(key).ok()?, decode_path(value).ok()?))
+            })
+            .collect::<Option<SessionData>>()?;
+        Some((data, UNIX_EPOCH + Duration::from_secs(expires)))
+    }
+
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) {
+        let Some(path) = self.path(id) else {
+            return;
+        };
+
+        let mut content = format!("{}\n", unix_time() + ttl.as_secs());
+        for (key, value) in data {
+            content.push_str(&format!("{}={}\n", encode_path_segment(key), encode_path_segment(value)));
+        }
+
+        if let Err(e) = fs::write(&path, content) {
+            error!("Failed to save session: {e}");
+        }
+        if sweep_due(&self.last_sweep) {
+            self.sweep();
+        }
+    }
+
+    fn destroy(&self, id: &str) {
+        if let Some(path) = self.path(id) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+struct SessionState {
+    id: Option<String>,
+    data: SessionData,
+    expires: Option<SystemTime>,
+    modified: bool,
+    regenerate: bool,
+    destroyed: bool,
+}
+
+#[derive(Clone)]
+pub struct Session {
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl Session {
+    fn new(id: Option<String>, data: SessionData, expires: Option<SystemTime>) -> Self {
+        Session {
+            state: Arc::new(Mutex::new(SessionState {
+                id,
+                data,
+                expires,
+                modified: false,
+                regenerate: false,
+                destroyed: false,
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn id(&self) -> Option<String> {
+        self.state().id.clone()
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.state().data.get(key).cloned()
+    }
+
+    pub fn set(&self, key: &str, value: &str) {
+        let mut state = self.state();
+        state.data.insert(key.to_string(), value.to_string());
+        state.modified = true;
+    }
+
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut state = self.state();
+        let removed = state.data.remove(key);
+        state.modified |= removed.is_some();
+        removed
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.data.clear();
+        state.modified = true;
+    }
+
+    pub fn regenerate(&self) {
+        self.state().regenerate = true;
+    }
+
+    pub fn destroy(&self) {
+        let mut state = self.state();
+        state.data.clear();
+        state.destroyed = true;
+    }
+}
+
+pub struct Sessions {
+    store: Box<dyn SessionStore>,
+    cookie_name: String,
+    ttl: Duration,
+    secure: bool,
+}
+
+impl Sessions {
+    pub fn new(store: impl SessionStore + 'static) -> Self {
+        Sessions {
+            store: Box::new(store),
+            cookie_name: "syne_session".to_string(),
+            ttl: Duration::from_secs(24 * 60 * 60),
+            secure: false,
+        }
+    }
+
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        self.cookie_name = name.to_string();
+        self
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    pub(crate) fn load(&self, request: &mut Request) {
+        let existing = request
+            .cookie(&self.cookie_name)
+            .and_then(|id| Some((id.to_string(), self.store.load(id)?)));
+
+        request.session = Some(match existing {
+            Some((id, (data, expires))) => Session::new(Some(id), data, Some(expires)),
+            None => Session::new(None, SessionData::new(), None),
+        });
+    }
+
+    pub(crate) fn commit(&self, request: &Request, response: &mut Response) {
+        let Some(session) = &request.session else {
+            return;
+        };
+        let mut state = session.state();
+
+        if state.destroyed || state.regenerate {
+            let previous = state.id.take();
+            if let Some(id) = &previous {
+                self.store.destroy(id);
+            }
+            if state.data.is_empty() {
+                if previous.is_some() {
+                    response.add_cookie(self.cookie("").removal());
+                }
+                return;
+            }
+            state.modified = true;
+        }
+
+        let refresh_due = state
+            .expires
+            .is_none_or(|expires| expires < SystemTime::now() + self.ttl / 2);
+        if !state.modified && (state.id.is_none() || !refresh_due) {
+            return;
+        }
+
+        let id = match &state.id {
+            Some(id) => id.clone(),
+            None => {
+                let id = base64_url_encode(&random_bytes(SESSION_ID_BYTES));
+                response.add_cookie(self.cookie(&id));
+                state.id = Some(id.clone());
+                id
+            }
+        };
+        self.store.save(&id, &state.data, self.ttl);
+        state.expires = Some(SystemTime::now() + self.ttl);
+        state.modified = false;
+        state.regenerate = false;
+        state.destroyed = false;
+    }
+
+    fn cookie(&self, value: &str) -> Cookie {
+        Cookie::new(&self.cookie_name, value)
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(self.secure)
+    }
+}
+
+fn sweep_due(last_sweep: &Mutex<Option<Instant>>) -> bool {
+    let mut last_sweep = last_sweep.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if last_sweep.is_some_and(|last_sweep| last_sweep.elapsed() < SWEEP_INTERVAL) {
+        return false;
+    }
+    *last_sweep = Some(Instant::now());
+    true
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(value: &str) -> SessionData {
+        SessionData::from([("user".to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn file_store_round_trip_and_sweep() {
+        let directory = std::env::temp_dir().join(format!("syne-sessions-{}", base64_url_encode(&random_bytes(8))));
+        let store = FileStore::new(&directory).unwrap();
+        fs::write(directory.join("abandoned.session"), "1\nuser=old\n").unwrap();
+        fs::write(directory.join("notes.txt"), "1\n").unwrap();
+
+        store.save("live", &data("alice"), Duration::from_secs(60));
+        let (loaded, expires) = store.load("live").unwrap();
+
+        assert_eq!(loaded, data("alice"));
+        assert!(expires > SystemTime::now());
+        assert!(!directory.join("abandoned.session").exists());
+        assert!(directory.join("notes.txt").exists());
+        assert!(store.load("../live").is_none());
+
+        store.destroy("live");
+        assert!(store.load("live").is_none());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn memory_store_expires_sessions() {
+        let store = MemoryStore::new();
+        store.save("expired", &data("old"), Duration::ZERO);
+        store.save("live", &data("alice"), Duration::from_secs(60));
+
+        assert!(store.load("expired").is_none());
+        assert_eq!(store.load("live").map(|(data, _)| data), Some(data("alice")));
+    }
+}
@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::compress::{accepts, add_vary};
+use crate::mime::MimeTypes;
+use crate::request::Request;
+use crate::response::Response;
+use crate::status::StatusCode;
+use crate::url::{decode_path, encode_path_segment};
+
+pub struct StaticFiles {
+    root: PathBuf,
+    symlinks: bool,
+    dotfiles: bool,
+    index_file: Option<String>,
+    directory_listing: bool,
+    fallback: Option<String>,
+    cache_control: Vec<(String, String)>,
+    precompressed: bool,
+}
+
+impl StaticFiles {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        StaticFiles {
+            root: root.into(),
+            symlinks: true,
+            dotfiles: false,
+            index_file: Some("index.html".to_string()),
+            directory_listing: false,
+            fallback: None,
+            cache_control: Vec::new(),
+            precompressed: false,
+        }
+    }
+
+    pub fn symlinks(mut self, allow: bool) -> Self {
+        self.symlinks = allow;
+        self
+    }
+
+    pub fn dotfiles(mut self, allow: bool) -> Self {
+        self.dotfiles = allow;
+        self
+    }
+
+    pub fn index_file(mut self, name: &str) -> Self {
+        self.index_file = Some(name.to_string());
+        self
+    }
+
+    pub fn no_index_file(mut self) -> Self {
+        self.index_file = None;
+        self
+    }
+
+    pub fn directory_listing(mut self, enabled: bool) -> Self {
+        self.directory_listing = enabled;
+        self
+    }
+
+    pub fn fallback(mut self, file: &str) -> Self {
+        self.fallback = Some(file.to_string());
+        self
+    }
+
+    pub fn cache_control(mut self, extension: &str, value: &str) -> Self {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+        self.cache_control.retain(|(existing, _)| *existing != extension);
+        self.cache_control.push((extension, value.to_string()));
+        self
+    }
+
+    pub fn precompressed(mut self, enabled: bool) -> Self {
+        self.precompressed = enabled;
+        self
+    }
+
+    pub(crate) fn serve(&self, request: &Request, relative_path: &str, mime_types: &MimeTypes) -> Response {
+        let path = match self.resolve(relative_path) {
+            Ok(Some(path)) => path,
+            Ok(None) => return self.not_found(request, mime_types),
+            Err(()) => return Response::with_status(StatusCode::BAD_REQUEST, "Invalid path"),
+        };
+
+        if path.is_dir() {
+            return self.serve_directory(request, &path, mime_types);
+        }
+
+        self.serve_file(request, &path, mime_types)
+    }
+
+    fn serve_directory(&self, request: &Request, directory: &Path, mime_types: &MimeTypes) -> Response {
+        if !request.path.ends_with('/') {
+            let mut location = format!("{}/", request.path);
+            if !request.query_string.is_empty() {
+                location = format!("{location}?{}", request.query_string);
+            }
+            return Response::permanent_redirect(&location);
+        }
+
+        if let Some(index_file) = &self.index_file {
+            let index = directory.join(index_file);
+            if index.is_file() && self.contained(&index) {
+                return self.serve_file(request, &index, mime_types);
+            }
+        }
+
+        if self.directory_listing {
+            return self.listing(request, directory);
+        }
+
+        self.not_found(request, mime_types)
+    }
+
+    fn serve_file(&self, request: &Request, path: &Path, mime_types: &MimeTypes) -> Response {
+        let compressed = self.compressed_sibling(path);
+        let mut response = match &compressed {
+            Some(compressed) if accepts(request, "gzip") => {
+                let mut response = Response::file(compressed);
+                response.set_header("Content-Encoding", "gzip");
+                response
+            }
+            _ => Response::file(path),
+        };
+
+        if response.status_code == 200 {
+            response.set_header("Content-Type", &mime_types.for_path(path));
+            if compressed.is_some() {
+                add_vary(&mut response, "Accept-Encoding");
+            }
+
+            let extension = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.to_ascii_lowercase());
+            let rule = self
+                .cache_control
+                .iter()
+                .find(|(rule, _)| Some(rule) == extension.as_ref());
+            if let Some((_, value)) = rule {
+                response.set_header("Cache-Control", value);
+            }
+        }
+
+        response
+    }
+
+    fn compressed_sibling(&self, path: &Path) -> Option<PathBuf> {
+        if !self.precompressed {
+            return None;
+        }
+        let mut compressed = path.as_os_str().to_owned();
+        compressed.push(".gz");
+        let compressed = PathBuf::from(compressed);
+        (compressed.is_file() && self.contained(&compressed)).then_some(compressed)
+    }
+
+    fn not_found(&self, request: &Request, mime_types: &MimeTypes) -> Response {
+        let fallback = self
+            .fallback
+            .as_deref()
+            .filter(|_| matches!(request.method.as_str(), "GET" | "HEAD"))
+            .and_then(|fallback| self.resolve(fallback).ok().flatten());
+
+        match fallback {
+            Some(path) if path.is_file() => self.serve_file(request, &path, mime_types),
+            _ => Response::with_status(StatusCode::NOT_FOUND, "File not found"),
+        }
+    }
+
+    fn listing(&self, request: &Request, directory: &Path) -> Response {
+        let Ok(entries) = fs::read_dir(directory) else {
+            return Response::with_status(StatusCode::INTERNAL_SERVER_ERROR, "Could not read the directory");
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                if name.starts_with('.') && !self.dotfiles {
+                    return None;
+                }
+                let is_dir = entry.file_type().ok()?.is_dir();
+                Some(if is_dir { format!("{name}/") } else { name })
+            })
+            .collect();
+        names.sort();
+
+        let title = escape_html(&request.path);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n<body>\n<h1>Index of {title}</h1>\n<ul>\n"
+        );
+        if request.path != "/" {
+            html.push_str("<li><a href=\"../\">../</a></li>\n");
+        }
+        for name in names {
+            let href = match name.strip_suffix('/') {
+                Some(directory) => format!("{}/", encode_path_segment(directory)),
+                None => encode_path_segment(&name),
+            };
+            html.push_str(&format!("<li><a href=\"{href}\">{}</a></li>\n", escape_html(&name)));
+        }
+        html.push_str("</ul>\n</body>\n</html>\n");
+
+        Response::html(200, &html)
+    }
+
+    fn resolve(&self, relative_path: &str) -> Result<Option<PathBuf>, ()> {
+        let mut path = self.root.clone();
+
+        for segment in relative_path.split('/').filter(|segment| !segment.is_empty()) {
+            let segment = decode_path(segment).map_err(|_| ())?;
+            if segment == "." || segment == ".." || segment.contains(['/', '\\', '\0']) {
+                return Err(());
+            }
+            if segment.starts_with('.') && !self.dotfiles {
+                return Ok(None);
+            }
+
+            path.push(segment);
+            if !self.symlinks && fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+                return Ok(None);
+            }
+        }
+
+        Ok(self.contained(&path).then_some(path))
+    }
+
+    fn contained(&self, path: &Path) -> bool {
+        match (fs::canonicalize(&self.root), fs::canonicalize(path)) {
+            (Ok(root), Ok(path)) => path.starts_with(root),
+            _ => false,
+        }
+    }
+}
+
+pub(crate) fn strip_mount<'a>(path: &'a str, mount: &str) -> Option<&'a str> {
+    let mount = mount.trim_end_matches('/');
+    let rest = path.strip_prefix(mount)?;
+    (rest.is_empty() || rest.starts_with('/')).then_some(rest)
+}
+
+fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidEscape(usize),
+    InvalidUtf8,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidEscape(position) => write!(f, "invalid percent escape at byte {position}"),
+            DecodeError::InvalidUtf8 => write!(f, "percent-decoded bytes are not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+pub fn decode_path(input: &str) -> Result<String, DecodeError> {
+    let bytes = decode_bytes(input, false, true)?;
+    String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)
+}
+
+pub fn decode_path_lossy(input: &str) -> String {
+    let bytes = decode_bytes(input, false, false).unwrap_or_default();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+pub fn decode_query(input: &str) -> Result<String, DecodeError> {
+    let bytes = decode_bytes(input, true, true)?;
+    String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)
+}
+
+pub fn decode_query_lossy(input: &str) -> String {
+    let bytes = decode_bytes(input, true, false).unwrap_or_default();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+pub fn encode_path_segment(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+
+    for byte in input.bytes() {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+pub fn encode_query_component(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+
+    for byte in input.bytes() {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else if byte == b' ' {
+            encoded.push('+');
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+pub(crate) fn decode_unreserved(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(pair) = bytes.get(i + 1..i + 3)
+            && let Some(byte) = hex_pair(pair)
+        {
+            if is_unreserved(byte) {
+                decoded.push(byte);
+            } else {
+                decoded.push(b'%');
+                decoded.extend(pair.to_ascii_uppercase());
+            }
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8(decoded).unwrap_or_else(|_| input.to_string())
+}
+
+fn decode_bytes(input: &str, plus_as_space: bool, strict: bool) -> Result<Vec<u8>, DecodeError> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => match bytes.get(i + 1..i + 3).and_then(hex_pair) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                None if strict => return Err(DecodeError::InvalidEscape(i)),
+                None => decoded.push(b'%'),
+            },
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    Ok(decoded)
+}
+
+fn hex_pair(pair: &[u8]) -> Option<u8> {
+    let digit = |byte: u8| (byte as char).to_digit(16);
+    Some((digit(pair[0])? << 4 | digit(pair[1])?) as u8)
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_paths() {
+        assert_eq!(decode_path("a%20b%2Fc"), Ok("a b/c".to_string()));
+        assert_eq!(decode_path("a+b"), Ok("a+b".to_string()));
+        assert_eq!(decode_path("%e2%82%ac"), Ok("\u{20ac}".to_string()));
+        assert_eq!(decode_path("caf\u{e9}"), Ok("caf\u{e9}".to_string()));
+    }
+
+    #[test]
+    fn decodes_queries() {
+        assert_eq!(decode_query("a+b%2Bc"), Ok("a b+c".to_string()));
+        assert_eq!(decode_query("%3D%26"), Ok("=&".to_string()));
+        assert_eq!(decode_query_lossy("x+%zz"), "x %zz");
+    }
+
+    #[test]
+    fn rejects_invalid_escapes() {
+        assert_eq!(decode_path("%zz"), Err(DecodeError::InvalidEscape(0)));
+        assert_eq!(decode_path("ab%2"), Err(DecodeError::InvalidEscape(2)));
+        assert_eq!(decode_path("a%"), Err(DecodeError::InvalidEscape(1)));
+        assert_eq!(decode_path("%+5"), Err(DecodeError::InvalidEscape(0)));
+        assert_eq!(decode_query("%-1"), Err(DecodeError::InvalidEscape(0)));
+        assert_eq!(decode_path_lossy("%+5%2"), "%+5%2");
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        assert_eq!(decode_path("%FF"), Err(DecodeError::InvalidUtf8));
+        assert_eq!(decode_query("%C3"), Err(DecodeError::InvalidUtf8));
+        assert_eq!(decode_path("%ED%A0%80"), Err(DecodeError::InvalidUtf8));
+        assert_eq!(decode_path_lossy("a%FFb"), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn encodes_components() {
+        assert_eq!(encode_path_segment("a b/\u{fc}~-._"), "a%20b%2F%C3%BC~-._");
+        assert_eq!(encode_query_component("a b+c&d=e"), "a+b%2Bc%26d%3De");
+
+        let original = "\u{20ac} 100% + tax/\u{1F600}?#";
+        assert_eq!(decode_path(&encode_path_segment(original)).as_deref(), Ok(original));
+        assert_eq!(decode_query(&encode_query_component(original)).as_deref(), Ok(original));
+    }
+
+    #[test]
+    fn normalizes_unreserved_escapes() {
+        assert_eq!(decode_unreserved("%7e%41%2d%2E"), "~A-.");
+        assert_eq!(decode_unreserved("a%2fb%e2%82%ac"), "a%2Fb%E2%82%AC");
+        assert_eq!(decode_unreserved("%2e"), ".");
+        assert_eq!(decode_unreserved("%+5%2%zz+"), "%+5%2%zz+");
+        assert_eq!(decode_unreserved("caf\u{e9}%20"), "caf\u{e9}%20");
+    }
+}